@@ -0,0 +1,219 @@
+use crate::envsub::{envsub, EnvSubError};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const IMPORT_PATH_PREFIX: char = '<';
+
+/// The generated script's own variables, `$bin` for the ELF and the `$reset` macro
+const RESERVED_VARIABLE_NAMES: &[&str] = &["bin", "reset"];
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("The field 'assets' cannot contain an empty string")]
+    Empty,
+    #[error("The asset file '{_0}' could not be found")]
+    NotFound(String),
+    #[error("Could not determine a file name for the asset '{_0}'")]
+    FileName(String),
+    #[error("The assets '{_0}' and '{_1}' would both be generated as '{_2}'")]
+    Duplicate(String, String, String),
+    #[error("The asset '{_0}' would overwrite the generated script '{_1}'")]
+    Script(String, String),
+    #[error(
+        "The asset '{_0}' would be assigned to '${_1}', which the generated script already uses"
+    )]
+    Reserved(String, String),
+    #[error("The asset '{_0}' can't be substituted into its own directory, use another output directory")]
+    InPlace(String),
+    #[error("Encountered an IO error while generating the asset '{_0}'. {_1}")]
+    Io(String, String),
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// A file copied into the output directory.
+/// When its path begins with '<', environment substitution is performed on its content.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Asset {
+    pub source: PathBuf,
+    pub file_name: String,
+    pub substitute: bool,
+}
+
+impl Asset {
+    pub fn new(asset_from_config: &str) -> Result<Self, AssetError> {
+        let desc = asset_from_config.trim();
+        let substitute = desc.starts_with(IMPORT_PATH_PREFIX);
+        let path = envsub(desc.trim_start_matches(IMPORT_PATH_PREFIX).trim())?;
+        if path.is_empty() {
+            return Err(AssetError::Empty);
+        }
+        let source = PathBuf::from(&path);
+        if !source.is_file() {
+            return Err(AssetError::NotFound(path));
+        }
+        let file_name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| AssetError::FileName(path.clone()))?
+            .to_owned();
+        Ok(Asset {
+            source,
+            file_name,
+            substitute,
+        })
+    }
+
+    /// The name of the variable holding the generated path, e.g. `wss_py` for `models/wss.py`.
+    /// Names can't start with a digit, so `3d.bin` is `_3d_bin`.
+    pub fn variable_name(&self) -> String {
+        let name = self.name();
+        if name.starts_with(|c: char| c.is_ascii_digit()) {
+            format!("_{name}")
+        } else {
+            name
+        }
+    }
+
+    /// The environment variable holding the generated path, e.g. `RENODE_RUN_ASSET_WSS_PY` for `models/wss.py`
+    pub fn env_var(&self) -> String {
+        format!("RENODE_RUN_ASSET_{}", self.name().to_ascii_uppercase())
+    }
+
+    fn name(&self) -> String {
+        self.file_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+
+    /// The path of the asset in the output directory
+    pub fn output_path(&self, output_dir: &Path) -> PathBuf {
+        output_dir.join(&self.file_name)
+    }
+
+    /// Copies, or substitutes, the asset into the output directory.
+    /// An asset that's already in the output directory isn't copied onto itself.
+    pub fn generate(&self, output_dir: &Path) -> Result<(), AssetError> {
+        let source = self.source.display().to_string();
+        let out_path = self.output_path(output_dir);
+        let in_place = out_path.exists()
+            && fs::canonicalize(&self.source).ok() == fs::canonicalize(&out_path).ok();
+        if in_place && self.substitute {
+            return Err(AssetError::InPlace(source));
+        } else if in_place {
+            return Ok(());
+        }
+        if self.substitute {
+            let content = fs::read_to_string(&self.source)
+                .map_err(|e| AssetError::Io(source.clone(), e.to_string()))?;
+            fs::write(out_path, envsub(&content)?)
+        } else {
+            fs::copy(&self.source, out_path).map(|_| ())
+        }
+        .map_err(|e| AssetError::Io(source, e.to_string()))
+    }
+}
+
+/// Resolves the configured assets, each must have a distinct file name
+/// that doesn't clash with the generated script's variables
+pub fn resolve(assets_from_config: &[String]) -> Result<Vec<Asset>, AssetError> {
+    let mut assets: Vec<Asset> = Vec::new();
+    for a in assets_from_config.iter() {
+        let asset = Asset::new(a)?;
+        if RESERVED_VARIABLE_NAMES.contains(&asset.variable_name().as_str()) {
+            return Err(AssetError::Reserved(
+                asset.source.display().to_string(),
+                asset.variable_name(),
+            ));
+        }
+        if let Some(other) = assets
+            .iter()
+            .find(|o| o.variable_name() == asset.variable_name())
+        {
+            return Err(AssetError::Duplicate(
+                other.source.display().to_string(),
+                asset.source.display().to_string(),
+                asset.file_name,
+            ));
+        }
+        assets.push(asset);
+    }
+    Ok(assets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        fs::create_dir_all(&out).unwrap();
+        let model = dir.path().join("wss.py");
+        let blob = dir.path().join("blob.bin");
+        fs::write(&model, "port = ${GENERATED_ASSETS_PORT:-1234}\n").unwrap();
+        fs::write(&blob, "${NOT_SUBSTITUTED}").unwrap();
+
+        let assets =
+            resolve(&[format!("< {}", model.display()), blob.display().to_string()]).unwrap();
+        assert_eq!(assets[0].variable_name(), "wss_py");
+        assert_eq!(assets[0].env_var(), "RENODE_RUN_ASSET_WSS_PY");
+        for a in assets.iter() {
+            a.generate(&out).unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(out.join("wss.py")).unwrap(),
+            "port = 1234\n"
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("blob.bin")).unwrap(),
+            "${NOT_SUBSTITUTED}"
+        );
+
+        assert_eq!(
+            resolve(&[model.display().to_string(), model.display().to_string()]),
+            Err(AssetError::Duplicate(
+                model.display().to_string(),
+                model.display().to_string(),
+                "wss.py".to_owned()
+            ))
+        );
+        // Already in the output directory
+        let copied = resolve(&[out.join("blob.bin").display().to_string()]).unwrap();
+        copied[0].generate(&out).unwrap();
+        assert_eq!(
+            fs::read_to_string(out.join("blob.bin")).unwrap(),
+            "${NOT_SUBSTITUTED}"
+        );
+        let substituted = resolve(&[format!("< {}", out.join("wss.py").display())]).unwrap();
+        assert!(matches!(
+            substituted[0].generate(&out),
+            Err(AssetError::InPlace(_))
+        ));
+
+        let model_3d = dir.path().join("3d.bin");
+        fs::write(&model_3d, "").unwrap();
+        let assets = resolve(&[model_3d.display().to_string()]).unwrap();
+        assert_eq!(assets[0].variable_name(), "_3d_bin");
+        assert_eq!(assets[0].env_var(), "RENODE_RUN_ASSET_3D_BIN");
+
+        let reset = dir.path().join("reset");
+        fs::write(&reset, "").unwrap();
+        assert_eq!(
+            resolve(&[reset.display().to_string()]),
+            Err(AssetError::Reserved(
+                reset.display().to_string(),
+                "reset".to_owned()
+            ))
+        );
+
+        assert_eq!(
+            resolve(&["missing.bin".to_owned()]),
+            Err(AssetError::NotFound("missing.bin".to_owned()))
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
@@ -23,17 +24,114 @@ pub struct RenodeScriptConfig {
     pub variables: Vec<String>,
     pub platform_description: Option<String>,
     pub platform_descriptions: Vec<String>,
+    pub assets: Vec<String>,
     pub reset: Option<String>,
     pub start: Option<String>,
     pub pre_start_commands: Vec<String>,
     pub post_start_commands: Vec<String>,
+    pub semihosting: Option<SemihostingConfig>,
+    pub gdb: Option<GdbConfig>,
+    pub logging: Option<LoggingConfig>,
+    pub virtual_timeout: Option<String>,
+    pub socket_terminals: Vec<SocketTerminalConfig>,
+}
+
+/// A TCP port, either fixed or `"auto"` to have renode-run pick a free one
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PortConfig {
+    Auto,
+    Fixed(u16),
+}
+
+impl PortConfig {
+    /// The port number, unless it's yet to be allocated
+    pub fn fixed(&self) -> Option<u16> {
+        match self {
+            PortConfig::Auto => None,
+            PortConfig::Fixed(p) => Some(*p),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PortConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Port(u16),
+            Keyword(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Port(p) => Ok(PortConfig::Fixed(p)),
+            Raw::Keyword(k) if k == "auto" => Ok(PortConfig::Auto),
+            Raw::Keyword(k) => Err(de::Error::custom(format!(
+                "invalid port '{k}', expected a port number or \"auto\""
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SocketTerminalConfig {
+    pub uart: String,
+    pub port: PortConfig,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub level: Option<String>,
+    pub peripherals: BTreeMap<String, String>,
+    pub file: Option<String>,
+    pub file_level: Option<String>,
+    pub log_function_names: Vec<String>,
+    pub log_peripheral_access: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct SemihostingConfig {
+    pub cpu: String,
+}
+
+impl Default for SemihostingConfig {
+    fn default() -> Self {
+        SemihostingConfig {
+            cpu: "sysbus.cpu".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct GdbConfig {
+    pub port: PortConfig,
+    pub autostart_emulation: bool,
+    pub cpu_cluster: Option<String>,
+    pub gdb: String,
+    pub break_on_main: bool,
+}
+
+impl Default for GdbConfig {
+    fn default() -> Self {
+        GdbConfig {
+            port: PortConfig::Fixed(3333),
+            autostart_emulation: false,
+            cpu_cluster: None,
+            gdb: "gdb-multiarch".to_owned(),
+            break_on_main: false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Default)]
 #[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
 pub struct RenodeCliConfig {
     pub plain: bool,
-    pub port: Option<u16>,
+    pub port: Option<PortConfig>,
     pub disable_xwt: bool,
     pub hide_monitor: bool,
     pub hide_log: bool,
@@ -48,7 +146,7 @@ impl RenodeCliConfig {
         if self.plain {
             args.push("--plain".to_owned());
         }
-        if let Some(p) = self.port {
+        if let Some(p) = self.port.and_then(|p| p.fixed()) {
             args.push("--port".to_owned());
             args.push(p.to_string());
         }
@@ -85,5 +183,126 @@ pub struct AppConfig {
     pub omit_start: bool,
     pub environment_variables: Vec<(String, String)>,
     pub renode: Option<String>,
+    pub renode_version: Option<String>,
+    pub backend: Backend,
+    pub image: Option<String>,
     pub omit_out_dir_path: bool,
+    pub allow_unmapped_memory: bool,
+    pub rtt: Option<RttConfig>,
+    pub timeout: Option<String>,
+    pub test: Option<TestConfig>,
+    pub expect: Vec<ExpectConfig>,
+    pub renode_test: Option<String>,
+    pub interactive: Option<InteractiveConfig>,
+    pub watch: Option<WatchConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct WatchConfig {
+    pub build_command: String,
+    pub paths: Vec<String>,
+    pub poll_interval_ms: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            build_command: "cargo build".to_owned(),
+            paths: vec![
+                "src".to_owned(),
+                "build.rs".to_owned(),
+                "memory.x".to_owned(),
+            ],
+            poll_interval_ms: 500,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct InteractiveConfig {
+    pub uarts: Vec<String>,
+    pub history_file: Option<String>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    #[default]
+    Native,
+    Docker,
+    Podman,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExpectConfig {
+    pub uart: String,
+    pub line: String,
+    #[serde(default)]
+    pub timeout: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct TestConfig {
+    pub source: TestSource,
+    pub uart: Option<String>,
+    pub rtt_channel: u32,
+    pub format: TestFormat,
+    pub start_pattern: Option<String>,
+    pub pass_pattern: Option<String>,
+    pub fail_pattern: Option<String>,
+    pub end_pattern: Option<String>,
+    pub junit: Option<String>,
+    pub json: Option<String>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestSource {
+    #[default]
+    Uart,
+    Rtt,
+    Semihosting,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestFormat {
+    #[default]
+    DefmtTest,
+    Libtest,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct RttConfig {
+    pub control_block_symbol: String,
+    pub control_block_address: Option<u64>,
+    pub poll_interval_ms: u64,
+    pub up_channels: Vec<RttChannelConfig>,
+}
+
+impl Default for RttConfig {
+    fn default() -> Self {
+        RttConfig {
+            control_block_symbol: "_SEGGER_RTT".to_owned(),
+            control_block_address: None,
+            poll_interval_ms: 10,
+            up_channels: vec![RttChannelConfig {
+                channel: 0,
+                decoder: None,
+            }],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RttChannelConfig {
+    pub channel: u32,
+    #[serde(default)]
+    pub decoder: Option<String>,
 }
@@ -0,0 +1,297 @@
+use crate::monitor::{MonitorClient, MonitorError};
+use lazy_static::lazy_static;
+use regex::Regex;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::DefaultHistory, validate::Validator, Context, Editor, ExternalPrinter, Helper,
+};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+/// Renode's standard output is written here in interactive mode, relative to the output directory
+pub const RENODE_LOG_FILE_NAME: &str = "renode.log";
+
+/// Monitor commands offered for completion alongside the machine's peripherals
+const COMMANDS: &[&str] = &[
+    "emulation",
+    "help",
+    "logFile",
+    "logLevel",
+    "mach",
+    "machine",
+    "macro",
+    "pause",
+    "peripherals",
+    "quit",
+    "runMacro",
+    "showAnalyzer",
+    "start",
+    "sysbus",
+    "using",
+];
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum ConsoleError {
+    #[error("The interactive console requires the renode monitor 'port' to be configured")]
+    MissingMonitorPort,
+    #[error("Line editor error. {_0}")]
+    Editor(String),
+    #[error(transparent)]
+    Monitor(#[from] MonitorError),
+}
+
+impl From<ReadlineError> for ConsoleError {
+    fn from(e: ReadlineError) -> Self {
+        ConsoleError::Editor(e.to_string())
+    }
+}
+
+/// A line-editing monitor prompt, with history and completion of peripheral and machine names
+pub struct Console {
+    editor: Editor<ConsoleHelper, DefaultHistory>,
+    history_file: Option<PathBuf>,
+}
+
+impl Console {
+    pub fn new(history_file: Option<PathBuf>) -> Result<Self, ConsoleError> {
+        let mut editor = Editor::new()?;
+        editor.set_helper(Some(ConsoleHelper::default()));
+        if let Some(p) = history_file.as_ref() {
+            // There's no history on the first run
+            let _ = editor.load_history(p);
+        }
+        Ok(Console {
+            editor,
+            history_file,
+        })
+    }
+
+    /// A sink that prints above the prompt instead of clobbering it, i.e. for UART output.
+    /// Falls back to stdout when there's no terminal.
+    pub fn printer(&mut self) -> Box<dyn Write + Send> {
+        match self.editor.create_external_printer() {
+            Ok(printer) => Box::new(PrinterSink {
+                printer,
+                pending: Vec::new(),
+            }),
+            Err(e) => {
+                log::debug!("Printing to stdout instead of above the prompt. {e}");
+                Box::new(io::stdout())
+            }
+        }
+    }
+
+    /// Reads commands and prints their responses until the user quits or renode exits
+    pub fn run(&mut self, mon: &mut MonitorClient) -> Result<(), ConsoleError> {
+        self.refresh_completions(mon);
+        loop {
+            let line = match self.editor.readline(&format!("({}) ", mon.prompt())) {
+                Ok(l) => l,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            };
+            let cmd = line.trim();
+            if cmd.is_empty() {
+                continue;
+            }
+            self.editor.add_history_entry(cmd)?;
+            if cmd == "quit" || cmd == "q" {
+                break;
+            }
+
+            let resp = match mon.execute_raw(cmd) {
+                Ok(r) => r,
+                Err(MonitorError::Closed) => {
+                    eprintln!("renode-run: renode exited");
+                    break;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if !resp.output.is_empty() {
+                println!("{}", resp.output);
+            }
+            // Commands can add machines and peripherals
+            self.refresh_completions(mon);
+        }
+
+        if let Some(p) = self.history_file.as_ref() {
+            if let Err(e) = self.editor.save_history(p) {
+                log::warn!(
+                    "Failed to save the console history to '{}'. {e}",
+                    p.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn refresh_completions(&mut self, mon: &mut MonitorClient) {
+        let mut names = Vec::new();
+        if let Ok(out) = mon.execute("peripherals") {
+            names = parse_peripherals(&out);
+        }
+        if let Ok(out) = mon.execute("mach") {
+            names.extend(parse_machines(&out));
+        }
+        names.push(mon.prompt().to_owned());
+        if let Some(h) = self.editor.helper_mut() {
+            h.names = names;
+        }
+    }
+}
+
+#[derive(Default)]
+struct ConsoleHelper {
+    names: Vec<String>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(|c: char| c.is_whitespace() || c == '"')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let word = &line[start..pos];
+        let mut candidates: Vec<String> = COMMANDS
+            .iter()
+            .map(|c| c.to_string())
+            .chain(self.names.iter().cloned())
+            .filter(|c| c.starts_with(word))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
+
+/// Forwards whole lines to the line editor's external printer
+struct PrinterSink<P: ExternalPrinter> {
+    printer: P,
+    pending: Vec<u8>,
+}
+
+impl<P: ExternalPrinter> Write for PrinterSink<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(i) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=i).collect();
+            let line = String::from_utf8_lossy(&line)
+                .trim_end_matches(['\r', '\n'])
+                .to_owned();
+            self.printer
+                .print(line)
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Parses the tree printed by the `peripherals` command into the peripherals' full names,
+/// e.g. `sysbus.usart3`
+fn parse_peripherals(out: &str) -> Vec<String> {
+    lazy_static! {
+        static ref PERIPHERAL_RE: Regex =
+            Regex::new(r"^([\s│├└─]*)([A-Za-z_][\w-]*) \([^)]*\)\s*$")
+                .expect("Could not construct peripheral Regex");
+    }
+    // (depth, name) of the current peripheral's ancestors
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut names = Vec::new();
+    for line in out.lines() {
+        let Some(caps) = PERIPHERAL_RE.captures(line) else {
+            continue;
+        };
+        let depth = caps[1].chars().count();
+        while stack.last().is_some_and(|(d, _)| *d >= depth) {
+            stack.pop();
+        }
+        stack.push((depth, caps[2].to_owned()));
+        names.push(
+            stack
+                .iter()
+                .map(|(_, n)| n.as_str())
+                .collect::<Vec<_>>()
+                .join("."),
+        );
+    }
+    names
+}
+
+/// Parses the machine names listed by the `mach` command, e.g. `0: machine-0 [current]`
+fn parse_machines(out: &str) -> Vec<String> {
+    lazy_static! {
+        static ref MACHINE_RE: Regex = Regex::new(r"^\s*\d+:\s*(.*?)(\s*\[current\])?\s*$")
+            .expect("Could not construct machine Regex");
+    }
+    out.lines()
+        .filter_map(|line| MACHINE_RE.captures(line))
+        .map(|caps| caps[1].to_owned())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peripherals() {
+        let out = "\
+Available peripherals:
+  sysbus (SystemBus)
+  │
+  ├── cpu (CortexM)
+  │     Slot: 0
+  │
+  ├── i2c1 (STM32F4_I2C)
+  │   │ <0x40005400, 0x400057FF>
+  │   │
+  │   └── sensor (BME280)
+  │         Address: 0x76
+  │
+  └── usart3 (STM32_UART)
+        <0x40004800, 0x40004BFF>
+";
+        assert_eq!(
+            parse_peripherals(out),
+            vec![
+                "sysbus",
+                "sysbus.cpu",
+                "sysbus.i2c1",
+                "sysbus.i2c1.sensor",
+                "sysbus.usart3"
+            ]
+        );
+    }
+
+    #[test]
+    fn machines() {
+        let out = "Available machines:\n\t0: machine-0 [current]\n\t1: stm32f4 discovery\n";
+        assert_eq!(parse_machines(out), vec!["machine-0", "stm32f4 discovery"]);
+        assert!(parse_machines("No machines loaded.").is_empty());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The working directory is mounted here, so relative paths resolve the same way in the container
+pub const WORK_DIR: &str = "/renode-run/work";
+/// Directories outside of the working directory are mounted under here
+const MOUNT_DIR: &str = "/renode-run/mnt";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum ContainerError {
+    #[error("The '{_0}' backend requires an 'image' to be configured")]
+    MissingImage(String),
+    #[error("Failed to resolve the path '{_0}'. {_1}")]
+    Path(String, String),
+    #[error("'renode-run ide' launches renode natively and doesn't support the '{_0}' backend")]
+    Ide(String),
+}
+
+/// Runs renode in a docker or podman container
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Container {
+    engine: String,
+    image: String,
+    /// (host, container) directories, longest host path first
+    mounts: Vec<(PathBuf, String)>,
+    ports: Vec<u16>,
+    env: Vec<(String, String)>,
+}
+
+impl Container {
+    /// Mounts the working directory and each of the directories that aren't inside of it
+    pub fn new<P: AsRef<Path>>(
+        engine: &str,
+        image: &str,
+        work_dir: P,
+        dirs: &[PathBuf],
+        ports: Vec<u16>,
+        env: Vec<(String, String)>,
+    ) -> Result<Self, ContainerError> {
+        let work_dir = canonicalize(work_dir.as_ref())?;
+        let mut mounts = vec![(work_dir.clone(), WORK_DIR.to_owned())];
+        for d in dirs.iter() {
+            let d = canonicalize(d)?;
+            if !mounts.iter().any(|(host, _)| d.starts_with(host)) {
+                let container_dir = format!("{MOUNT_DIR}/{}", mounts.len());
+                mounts.push((d, container_dir));
+            }
+        }
+        mounts.sort_by_key(|(host, _)| std::cmp::Reverse(host.as_os_str().len()));
+        Ok(Container {
+            engine: engine.to_owned(),
+            image: image.to_owned(),
+            mounts,
+            ports,
+            env,
+        })
+    }
+
+    /// Replaces the host paths of the mounted directories with their paths in the container
+    pub fn rewrite_paths(&self, text: &str) -> String {
+        let mut text = text.to_owned();
+        for (host, container) in self.mounts.iter() {
+            text = replace_path(&text, &host.display().to_string(), container);
+        }
+        text
+    }
+
+    /// The engine invocation that runs renode with the arguments
+    pub fn command(&self, renode_args: &[String]) -> Command {
+        let mut cmd = Command::new(&self.engine);
+        cmd.args(["run", "--rm", "--interactive", "--init"]);
+        for (host, container) in self.mounts.iter() {
+            cmd.arg("--volume")
+                .arg(format!("{}:{container}", host.display()));
+        }
+        cmd.args(["--workdir", WORK_DIR]);
+        for p in self.ports.iter() {
+            cmd.arg("--publish").arg(format!("127.0.0.1:{p}:{p}"));
+        }
+        for (k, v) in self.env.iter() {
+            cmd.arg("--env").arg(format!("{k}={v}"));
+        }
+        cmd.arg(&self.image).arg("renode").args(renode_args);
+        cmd
+    }
+}
+
+fn canonicalize(p: &Path) -> Result<PathBuf, ContainerError> {
+    p.canonicalize()
+        .map_err(|e| ContainerError::Path(p.display().to_string(), e.to_string()))
+}
+
+/// Replaces whole path prefixes, i.e. `/a/b` in `/a/b/c` but not in `/a/bc`
+fn replace_path(text: &str, from: &str, to: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(from) {
+        let end = i + from.len();
+        let boundary = rest[end..]
+            .chars()
+            .next()
+            .map(|c| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+            .unwrap_or(true);
+        out.push_str(&rest[..i]);
+        out.push_str(if boundary { to } else { from });
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn path_rewrites() {
+        let work = tempfile::tempdir().unwrap();
+        let out = tempfile::tempdir().unwrap();
+        fs::create_dir_all(work.path().join("target")).unwrap();
+        let work_dir = work.path().canonicalize().unwrap();
+        let out_dir = out.path().canonicalize().unwrap();
+
+        let c = Container::new(
+            "docker",
+            "antmicro/renode",
+            &work_dir,
+            &[work_dir.join("target"), out_dir.clone()],
+            vec![3333],
+            Vec::new(),
+        )
+        .unwrap();
+
+        let script = format!(
+            "path add @{out}\n$bin = @{work}/target/app\nmachine LoadPlatformDescription @{work}2/x.repl\n",
+            out = out_dir.display(),
+            work = work_dir.display()
+        );
+        assert_eq!(
+            c.rewrite_paths(&script),
+            format!(
+                "path add @{MOUNT_DIR}/1\n$bin = @{WORK_DIR}/target/app\nmachine LoadPlatformDescription @{}2/x.repl\n",
+                work_dir.display()
+            )
+        );
+
+        let args: Vec<String> = c
+            .command(&["/renode-run/work/emulate.resc".to_owned()])
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        assert!(args.contains(&format!("{}:{WORK_DIR}", work_dir.display())));
+        assert!(args.contains(&"127.0.0.1:3333:3333".to_owned()));
+        assert_eq!(args.last().unwrap(), "/renode-run/work/emulate.resc");
+    }
+}
@@ -0,0 +1,424 @@
+use crate::config::{Backend, RenodeCliConfig, RenodeRunConfig};
+use crate::envsub::{self, envsub};
+use crate::memory;
+use crate::ports;
+use crate::renode;
+use crate::repl;
+use crate::types::{PlatformDescription, PlatformDescriptionError, PlatformDescriptionKind};
+use derive_more::Display;
+use serde::Deserialize;
+use std::{
+    env, fmt, fs,
+    net::TcpListener,
+    path::{Path, PathBuf},
+};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Status {
+    #[display("ok")]
+    Ok,
+    #[display("warning")]
+    Warning,
+    #[display("error")]
+    Error,
+}
+
+/// The outcome of one diagnostic, with a suggested fix when it didn't pass
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    pub fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Check {
+            name: name.into(),
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub fn warning(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Check {
+            name: name.into(),
+            status: Status::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    pub fn error(
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Check {
+            name: name.into(),
+            status: Status::Error,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn push(&mut self, check: Check) {
+        self.checks.push(check);
+    }
+
+    pub fn extend(&mut self, checks: impl IntoIterator<Item = Check>) {
+        self.checks.extend(checks);
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+
+    pub fn success(&self) -> bool {
+        self.count(Status::Error) == 0
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.checks.iter() {
+            writeln!(f, "[{:^7}] {}: {}", c.status.to_string(), c.name, c.detail)?;
+            if let Some(fix) = c.fix.as_ref() {
+                writeln!(f, "{:9} fix: {fix}", "")?;
+            }
+        }
+        write!(
+            f,
+            "\n{} errors, {} warnings",
+            self.count(Status::Error),
+            self.count(Status::Warning)
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Default)]
+struct RawMetadata {
+    renode: Option<serde_json::Value>,
+}
+
+/// Checks every string in the config for substitutions of unset environment variables.
+/// The variables renode-run sets itself (`RENODE_RUN_*`) are skipped.
+pub fn env_vars(config_file: &Path) -> Check {
+    let name = "environment";
+    let raw = fs::read(config_file).ok().and_then(|bytes| {
+        cargo_toml::Manifest::<RawMetadata>::from_slice_with_metadata(&bytes)
+            .ok()?
+            .package?
+            .metadata?
+            .renode
+    });
+    let mut missing = Vec::new();
+    if let Some(raw) = raw.as_ref() {
+        visit_strings(raw, &mut |s| {
+            for var in envsub::missing_vars(s) {
+                if !var.starts_with("RENODE_RUN_") && !missing.contains(&var) {
+                    missing.push(var);
+                }
+            }
+        });
+    }
+    if missing.is_empty() {
+        Check::ok(name, "every substituted variable is set")
+    } else {
+        Check::error(
+            name,
+            format!("unset variables {}", missing.join(", ")),
+            "export them, set them in 'environment-variables' or give a default with ${NAME:-default}",
+        )
+    }
+}
+
+fn visit_strings(v: &serde_json::Value, f: &mut impl FnMut(&str)) {
+    match v {
+        serde_json::Value::String(s) => f(s),
+        serde_json::Value::Array(a) => a.iter().for_each(|v| visit_strings(v, f)),
+        serde_json::Value::Object(o) => o.values().for_each(|v| visit_strings(v, f)),
+        _ => (),
+    }
+}
+
+/// Resolves each platform description, checking `@platforms/...` paths against the renode installation
+pub fn platform_descriptions(cfg: &RenodeRunConfig, renode_root: Option<&Path>) -> Vec<Check> {
+    let descs: Vec<&String> = cfg
+        .resc
+        .platform_description
+        .iter()
+        .chain(cfg.resc.platform_descriptions.iter())
+        .collect();
+    if descs.is_empty() {
+        return vec![Check::error(
+            "platform",
+            "no platform descriptions are configured",
+            "add 'platform-descriptions', e.g. [\"@platforms/boards/stm32f4_discovery-kit.repl\"]",
+        )];
+    }
+
+    let mut parsed = Vec::new();
+    let mut checks: Vec<Check> = descs
+        .into_iter()
+        .map(|d| {
+            let summary = d.trim().lines().next().unwrap_or_default();
+            let name = format!("platform '{summary}'");
+            let reserved: Vec<String> = parsed
+                .iter()
+                .flat_map(PlatformDescription::imports)
+                .map(|i| i.file_name.clone())
+                .collect();
+            let desc = PlatformDescription::new(d, &reserved);
+            if let Ok(p) = desc.as_ref() {
+                parsed.push(p.clone());
+            }
+            match desc {
+                Ok(p) if *p.kind() == PlatformDescriptionKind::Internal => match renode_root {
+                    Some(root) => match p.check_internal(root) {
+                        Ok(()) => Check::ok(name, format!("found in '{}'", root.display())),
+                        Err(e) => Check::error(
+                            name,
+                            e.to_string(),
+                            "list the available platforms with 'renode-run platforms'",
+                        ),
+                    },
+                    None => Check::warning(
+                        name,
+                        "can't check renode's platforms without its installation directory",
+                        "fix the renode check, or set RENODE_ROOT",
+                    ),
+                },
+                Ok(p) => Check::ok(name, p.kind().to_string()),
+                Err(e @ PlatformDescriptionError::LocalFileNotFound(_)) => Check::error(
+                    name,
+                    e.to_string(),
+                    "local paths are relative to the directory renode-run is run from",
+                ),
+                Err(e @ PlatformDescriptionError::EnvSub(_)) => {
+                    Check::error(name, e.to_string(), "set the variable or give it a default")
+                }
+                Err(e) => Check::error(name, e.to_string(), "fix the platform description"),
+            }
+        })
+        .collect();
+
+    let findings = repl::check(&parsed, renode_root);
+    if findings.errors.is_empty() && findings.warnings.is_empty() {
+        checks.push(Check::ok(
+            "platform contents",
+            "no syntax errors, duplicate peripherals or overlapping sysbus ranges",
+        ));
+    }
+    let fix = "fix the platform description at the reported line";
+    checks.extend(
+        findings
+            .errors
+            .into_iter()
+            .map(|e| Check::error("platform contents", e.to_string(), fix)),
+    );
+    checks.extend(
+        findings
+            .warnings
+            .into_iter()
+            .map(|w| Check::warning("platform contents", w.to_string(), fix)),
+    );
+    checks
+}
+
+/// Checks the ELF's load segments are within the platform's memory
+pub fn memory(
+    descs: &[PlatformDescription],
+    renode_root: Option<&Path>,
+    elf: &Path,
+    allow_unmapped: bool,
+) -> Check {
+    let name = "memory";
+    match memory::check_elf(descs, renode_root, elf) {
+        Ok(Some(checked)) if !checked.unmapped.is_empty() => {
+            let unmapped = checked
+                .unmapped
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("; ");
+            if allow_unmapped {
+                Check::warning(name, unmapped, "check the script's commands add the memory")
+            } else {
+                Check::error(
+                    name,
+                    unmapped,
+                    "link the application for the platform's memory, or set 'allow-unmapped-memory' if the script's commands add it",
+                )
+            }
+        }
+        Ok(Some(checked)) => {
+            let used: Vec<String> = checked
+                .usage
+                .0
+                .iter()
+                .filter(|u| u.used > 0)
+                .map(|u| format!("{} {} bytes", u.region.name, u.used))
+                .collect();
+            Check::ok(name, format!("the ELF fits, using {}", used.join(", ")))
+        }
+        Ok(None) => Check::warning(
+            name,
+            "can't check the ELF without the platform's complete memory map",
+            "fix the renode check or the platform descriptions",
+        ),
+        Err(e) => Check::error(
+            name,
+            e.to_string(),
+            "link the application for the platform's memory, or pick the platform it's built for",
+        ),
+    }
+}
+
+/// Checks the directory is writable by creating a temporary file in it,
+/// or in the closest existing parent when renode-run would have to create it
+pub fn output_dir(dir: &Path) -> Check {
+    let name = "output directory";
+    let existing = dir
+        .ancestors()
+        .find(|d| d.is_dir())
+        .unwrap_or(Path::new("."));
+    match tempfile::tempfile_in(existing) {
+        Ok(_) => Check::ok(name, format!("'{}' is writable", dir.display())),
+        Err(e) => Check::error(
+            name,
+            format!("'{}' isn't writable. {e}", dir.display()),
+            "use --output with a writable directory",
+        ),
+    }
+}
+
+/// Checks the fixed ports are free, "auto" ports are always available
+pub fn ports(cfg: &RenodeRunConfig) -> Vec<Check> {
+    let fixed: Vec<Option<u16>> = [cfg.cli.port, cfg.resc.gdb.as_ref().map(|g| g.port)]
+        .into_iter()
+        .flatten()
+        .chain(cfg.resc.socket_terminals.iter().map(|t| t.port))
+        .map(|p| p.fixed())
+        .collect();
+    let mut cfg = cfg.clone();
+    let resolved = match ports::resolve(&mut cfg) {
+        Ok(r) => r,
+        Err(e) => return vec![Check::error("ports", e.to_string(), "fix the port config")],
+    };
+
+    resolved
+        .into_iter()
+        .zip(fixed)
+        .map(|(p, fixed)| {
+            let name = format!("{} port", p.name);
+            match fixed {
+                None => Check::ok(name, "auto"),
+                Some(port) => match TcpListener::bind(("127.0.0.1", port)) {
+                    Ok(_) => Check::ok(name, format!("{port} is free")),
+                    Err(e) => Check::error(
+                        name,
+                        format!("{port} is unavailable. {e}"),
+                        "stop the process using it (e.g. a previous renode), pick another port or use \"auto\"",
+                    ),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Checks the gdb used by `renode-run gdb` can be found, it's only required when the GDB server is configured
+pub fn gdb(cfg: &RenodeRunConfig) -> Check {
+    let name = "gdb";
+    let gdb_config = cfg.resc.gdb.clone().unwrap_or_default();
+    let gdb = match envsub(&gdb_config.gdb) {
+        Ok(g) => PathBuf::from(g),
+        Err(e) => return Check::error(name, e.to_string(), "set the variable in 'gdb.gdb'"),
+    };
+    let found = if gdb.components().count() > 1 {
+        gdb.is_file().then_some(gdb.clone())
+    } else {
+        renode::find_in_path(&gdb)
+    };
+    let fix = "install gdb-multiarch or set 'gdb.gdb' to an ARM capable gdb";
+    match found {
+        Some(path) => Check::ok(name, format!("found '{}'", path.display())),
+        None if cfg.resc.gdb.is_some() => {
+            Check::error(name, format!("'{}' not found", gdb.display()), fix)
+        }
+        None => Check::warning(
+            name,
+            format!(
+                "'{}' not found, it's needed by 'renode-run gdb'",
+                gdb.display()
+            ),
+            fix,
+        ),
+    }
+}
+
+/// Renode opens its monitor and analyzer windows unless 'disable-xwt' is set
+pub fn display(cli: &RenodeCliConfig, backend: Backend) -> Check {
+    let name = "display";
+    let fix = "set 'disable-xwt = true', optionally with 'console = true' to keep the monitor in the terminal";
+    if cli.disable_xwt {
+        Check::ok(name, "the GUI is disabled")
+    } else if backend != Backend::Native {
+        Check::warning(
+            name,
+            "containers don't have a display for renode's GUI",
+            fix,
+        )
+    } else if cfg!(target_os = "linux")
+        && env::var_os("DISPLAY").is_none()
+        && env::var_os("WAYLAND_DISPLAY").is_none()
+    {
+        Check::error(
+            name,
+            "neither DISPLAY nor WAYLAND_DISPLAY is set, renode's GUI can't open",
+            fix,
+        )
+    } else {
+        Check::ok(name, "available")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_var_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        fs::write(
+            &manifest,
+            r#"
+[package]
+name = "app"
+version = "0.1.0"
+
+[package.metadata.renode]
+platform-descriptions = ["${DOCTOR_NOT_SET_BOARD}", "${DOCTOR_NOT_SET_OTHER:-x.repl}"]
+variables = ["$port = ${RENODE_RUN_MONITOR_PORT}"]
+"#,
+        )
+        .unwrap();
+        let check = env_vars(&manifest);
+        assert_eq!(check.status, Status::Error);
+        assert_eq!(check.detail, "unset variables DOCTOR_NOT_SET_BOARD");
+    }
+}
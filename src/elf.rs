@@ -0,0 +1,183 @@
+use goblin::elf::{program_header::PT_LOAD, Elf};
+use std::{fs, path::Path};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum ElfError {
+    #[error("Encountered an IO error while reading the ELF file '{_0}'. {_1}")]
+    Io(String, String),
+    #[error("The file '{_0}' is not a valid ELF executable. {_1}")]
+    Parse(String, String),
+}
+
+/// Memory occupied by a loadable segment
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Segment {
+    pub start: u64,
+    pub size: u64,
+    /// Whether this is where the segment's contents are loaded, rather than where it runs,
+    /// e.g. the flash holding the initial values of `.data`
+    pub load: bool,
+}
+
+/// The parts of the application's ELF executable that renode-run cares about.
+#[derive(Clone, Debug)]
+pub struct ElfFile {
+    path: String,
+    data: Vec<u8>,
+}
+
+impl ElfFile {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ElfError> {
+        let p = path.as_ref().display().to_string();
+        let data = fs::read(path.as_ref()).map_err(|e| ElfError::Io(p.clone(), e.to_string()))?;
+        let elf = ElfFile { path: p, data };
+        // Fail early on files that aren't ELF executables
+        elf.parse()?;
+        Ok(elf)
+    }
+
+    /// Returns the address of the first symbol named `name`, if present.
+    pub fn symbol_address(&self, name: &str) -> Result<Option<u64>, ElfError> {
+        let elf = self.parse()?;
+        Ok(elf
+            .syms
+            .iter()
+            .find(|sym| elf.strtab.get_at(sym.st_name) == Some(name))
+            .map(|sym| sym.st_value))
+    }
+
+    /// Returns the addresses of every occurrence of the 16-bit Thumb instruction `insn`
+    /// in the executable sections.
+    ///
+    /// Only the code marked by the `$t` mapping symbols is searched, skipping the data (`$d`)
+    /// within it, and the code is walked one instruction at a time so the halves of 32-bit
+    /// instructions don't match. Sections without mapping symbols are searched from their start.
+    pub fn thumb_instruction_addresses(&self, insn: u16) -> Result<Vec<u64>, ElfError> {
+        let elf = self.parse()?;
+        let mut addrs = Vec::new();
+        for (index, sh) in elf
+            .section_headers
+            .iter()
+            .enumerate()
+            .filter(|(_, sh)| sh.is_executable())
+        {
+            let Some(data) = sh.file_range().and_then(|r| self.data.get(r)) else {
+                continue;
+            };
+            let section_end = sh.sh_addr + data.len() as u64;
+
+            // The start of each code (true) or data (false) range in the section
+            let mut mappings: Vec<(u64, bool)> = elf
+                .syms
+                .iter()
+                .filter(|sym| sym.st_shndx == index)
+                .filter_map(|sym| {
+                    let name = elf.strtab.get_at(sym.st_name)?;
+                    match name.split('.').next()? {
+                        "$t" => Some((sym.st_value, true)),
+                        "$a" | "$d" => Some((sym.st_value, false)),
+                        _ => None,
+                    }
+                })
+                .collect();
+            if mappings.is_empty() {
+                mappings.push((sh.sh_addr, true));
+            }
+            mappings.sort();
+
+            for (i, &(start, code)) in mappings.iter().enumerate() {
+                if !code {
+                    continue;
+                }
+                let end = mappings
+                    .get(i + 1)
+                    .map(|(next, _)| *next)
+                    .unwrap_or(section_end)
+                    .min(section_end);
+                let mut addr = start.max(sh.sh_addr);
+                while addr + 2 <= end {
+                    let offset = (addr - sh.sh_addr) as usize;
+                    let halfword = u16::from_le_bytes([data[offset], data[offset + 1]]);
+                    if halfword == insn {
+                        addrs.push(addr);
+                    }
+                    addr += if is_thumb32_prefix(halfword) { 4 } else { 2 };
+                }
+            }
+        }
+        Ok(addrs)
+    }
+
+    /// Returns the memory occupied by the `PT_LOAD` segments: the contents at their physical (load)
+    /// address, which renode loads them to, and the whole segment at its virtual (run) address.
+    pub fn load_segments(&self) -> Result<Vec<Segment>, ElfError> {
+        let elf = self.parse()?;
+        let mut segments = Vec::new();
+        for ph in elf.program_headers.iter().filter(|ph| ph.p_type == PT_LOAD) {
+            if ph.p_filesz > 0 && ph.p_paddr != ph.p_vaddr {
+                segments.push(Segment {
+                    start: ph.p_paddr,
+                    size: ph.p_filesz,
+                    load: true,
+                });
+            }
+            if ph.p_memsz > 0 {
+                segments.push(Segment {
+                    start: ph.p_vaddr,
+                    size: ph.p_memsz,
+                    load: false,
+                });
+            }
+        }
+        Ok(segments)
+    }
+
+    fn parse(&self) -> Result<Elf<'_>, ElfError> {
+        Elf::parse(&self.data).map_err(|e| ElfError::Parse(self.path.clone(), e.to_string()))
+    }
+}
+
+/// Whether the halfword is the first half of a 32-bit Thumb instruction
+fn is_thumb32_prefix(halfword: u16) -> bool {
+    matches!(halfword >> 11, 0b11101..=0b11111)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_segments() {
+        // .text in flash, .data loaded from flash into RAM with .bss after it, then .uninit
+        let elf = ElfFile::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/segments.elf"
+        ))
+        .unwrap();
+        assert_eq!(
+            elf.load_segments().unwrap(),
+            vec![
+                Segment {
+                    start: 0x0800_0000,
+                    size: 0x100,
+                    load: false,
+                },
+                Segment {
+                    start: 0x0800_0100,
+                    size: 0x10,
+                    load: true,
+                },
+                Segment {
+                    start: 0x2000_0000,
+                    size: 0x40,
+                    load: false,
+                },
+                Segment {
+                    start: 0x2000_0040,
+                    size: 0x400,
+                    load: false,
+                },
+            ]
+        );
+    }
+}
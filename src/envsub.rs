@@ -11,22 +11,22 @@ pub enum EnvSubError {
     EnvVarNotPresent(String),
 }
 
+lazy_static! {
+    // Matches the following patterns with named capture groups:
+    // * '${NAME}' : var = 'NAME'
+    // * '${NAME-default}' : var = 'NAME', def = 'default'
+    // * '${NAME:-default}' : var = 'NAME', def = 'default'
+    static ref ENVSUB_RE: Regex =
+        Regex::new(r"\$\{(?P<var>[a-zA-Z_][a-zA-Z0-9_]*)(:?-(?P<def>.*?))?\}")
+            .expect("Could not construct envsub Regex");
+}
+
 /// Substitute the values of environment variables.
 /// Supports the following substitution style expressions:
 /// * `${NAME}`
 /// * `${NAME-default}`
 /// * `${NAME:-default}`
 pub(crate) fn envsub(input: &str) -> Result<String, EnvSubError> {
-    lazy_static! {
-        // Matches the following patterns with named capture groups:
-        // * '${NAME}' : var = 'NAME'
-        // * '${NAME-default}' : var = 'NAME', def = 'default'
-        // * '${NAME:-default}' : var = 'NAME', def = 'default'
-        static ref ENVSUB_RE: Regex =
-            Regex::new(r"\$\{(?P<var>[a-zA-Z_][a-zA-Z0-9_]*)(:?-(?P<def>.*?))?\}")
-                .expect("Could not construct envsub Regex");
-    }
-
     replace_all(&ENVSUB_RE, input, |caps: &Captures| {
         // SAFETY: the regex requires a match for capture group 'var'
         let env_var = &caps["var"];
@@ -46,6 +46,16 @@ pub(crate) fn envsub(input: &str) -> Result<String, EnvSubError> {
         }
     })
 }
+
+/// The variables referenced without a default value that aren't set
+pub(crate) fn missing_vars(input: &str) -> Vec<String> {
+    ENVSUB_RE
+        .captures_iter(input)
+        .filter(|caps| caps.name("def").is_none() && env::var_os(&caps["var"]).is_none())
+        .map(|caps| caps["var"].to_owned())
+        .collect()
+}
+
 // This is essentially a fallible version of Regex::replace_all
 fn replace_all(
     re: &Regex,
@@ -96,4 +106,12 @@ mod tests {
             Err(EnvSubError::EnvVarNotPresent("NOT_SET_KEY".to_owned()))
         );
     }
+
+    #[test]
+    fn env_sub_missing_vars() {
+        assert_eq!(
+            missing_vars("${NOT_SET_KEY} ${NOT_SET_VAL:-1} ${CARGO_PKG_VERSION}"),
+            vec!["NOT_SET_KEY".to_owned()]
+        );
+    }
 }
@@ -0,0 +1,16 @@
+use std::{fmt::Write, path::Path};
+
+/// The gdb init script is written here, relative to the output directory
+pub const INIT_SCRIPT_FILE_NAME: &str = "gdb-init.gdb";
+
+/// Generates a gdb init script that loads symbols from the ELF and connects to renode
+pub fn init_script<P: AsRef<Path>>(elf: P, port: u16, break_on_main: bool) -> String {
+    let mut s = String::new();
+    // Infallible for String
+    let _ = writeln!(s, "file \"{}\"", elf.as_ref().display());
+    let _ = writeln!(s, "target remote :{port}");
+    if break_on_main {
+        let _ = writeln!(s, "break main");
+    }
+    s
+}
@@ -0,0 +1,452 @@
+use crate::config::{TestConfig, TestFormat};
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum HarnessError {
+    #[error("The test pattern '{_0}' is not a valid regular expression. {_1}")]
+    InvalidPattern(String, String),
+    #[error("The 'uart' test source requires test.uart to be configured")]
+    MissingUart,
+    #[error("The 'semihosting' test source requires semihosting to be configured")]
+    MissingSemihosting,
+    #[error("The 'rtt' test source requires RTT up-channel {_0} to be configured")]
+    MissingRttChannel(u32),
+}
+
+/// Regular expressions matched against each line of the test harness output
+#[derive(Clone, Debug)]
+struct Patterns {
+    /// A test started, captures `name`.
+    /// Without it only the results are known, not how long the tests took.
+    start: Option<Regex>,
+    /// A test passed, optionally captures `name`
+    pass: Option<Regex>,
+    /// A test failed, optionally captures `name` and `message`.
+    /// Failures without a name abort the test run (i.e. a panic).
+    fail: Regex,
+    /// All of the tests completed
+    end: Regex,
+}
+
+impl Patterns {
+    fn new(cfg: &TestConfig) -> Result<Self, HarnessError> {
+        let (start, pass, fail, end) = match cfg.format {
+            // defmt-test logs each test before running it and panics on the first failure
+            TestFormat::DefmtTest => (
+                Some(r"\(\d+/\d+\) running `(?P<name>[^`]+)`"),
+                None,
+                r"(?P<message>panicked at.*)",
+                r"all tests passed!",
+            ),
+            // libtest only prints a line once each test is done
+            TestFormat::Libtest => (
+                None,
+                Some(r"^test (?P<name>\S+) \.\.\. ok"),
+                r"^test (?P<name>\S+) \.\.\. FAILED(?P<message>.*)",
+                r"^test result: ",
+            ),
+        };
+        let re = |s: &str| {
+            Regex::new(s).map_err(|e| HarnessError::InvalidPattern(s.to_owned(), e.to_string()))
+        };
+        Ok(Patterns {
+            start: cfg.start_pattern.as_deref().or(start).map(re).transpose()?,
+            pass: cfg.pass_pattern.as_deref().or(pass).map(re).transpose()?,
+            fail: re(cfg.fail_pattern.as_deref().unwrap_or(fail))?,
+            end: re(cfg.end_pattern.as_deref().unwrap_or(end))?,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub struct TestCase {
+    pub name: String,
+    pub status: TestStatus,
+    #[serde(
+        serialize_with = "serialize_secs",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub duration: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TestReport {
+    pub name: String,
+    /// Whether the harness reported the end of the test run
+    pub complete: bool,
+    pub tests: Vec<TestCase>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.count(TestStatus::Passed)
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(TestStatus::Failed)
+    }
+
+    pub fn success(&self) -> bool {
+        self.complete && self.failed() == 0
+    }
+
+    fn count(&self, status: TestStatus) -> usize {
+        self.tests.iter().filter(|t| t.status == status).count()
+    }
+
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Summary<'a> {
+            name: &'a str,
+            success: bool,
+            complete: bool,
+            passed: usize,
+            failed: usize,
+            tests: &'a [TestCase],
+        }
+        let summary = Summary {
+            name: &self.name,
+            success: self.success(),
+            complete: self.complete,
+            passed: self.passed(),
+            failed: self.failed(),
+            tests: &self.tests,
+        };
+        // SAFETY: the summary only contains types that serialize infallibly
+        serde_json::to_string_pretty(&summary).unwrap()
+    }
+
+    pub fn to_junit(&self) -> String {
+        let durations: Vec<Duration> = self.tests.iter().filter_map(|t| t.duration).collect();
+        let total = (!durations.is_empty()).then(|| durations.iter().sum());
+        let mut s = String::new();
+        // Infallible for String
+        let _ = writeln!(s, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            s,
+            r#"<testsuites tests="{}" failures="{}"{}>"#,
+            self.tests.len(),
+            self.failed(),
+            time_attr(total)
+        );
+        let _ = writeln!(
+            s,
+            r#"  <testsuite name="{}" tests="{}" failures="{}"{}>"#,
+            xml_escape(&self.name),
+            self.tests.len(),
+            self.failed(),
+            time_attr(total)
+        );
+        for t in self.tests.iter() {
+            let _ = write!(
+                s,
+                r#"    <testcase name="{}" classname="{}"{}"#,
+                xml_escape(&t.name),
+                xml_escape(&self.name),
+                time_attr(t.duration)
+            );
+            match t.status {
+                TestStatus::Passed => {
+                    let _ = writeln!(s, "/>");
+                }
+                TestStatus::Failed => {
+                    let _ = writeln!(s, ">");
+                    let _ = writeln!(
+                        s,
+                        r#"      <failure message="{}"/>"#,
+                        xml_escape(t.message.as_deref().unwrap_or("failed"))
+                    );
+                    let _ = writeln!(s, "    </testcase>");
+                }
+            }
+        }
+        let _ = writeln!(s, "  </testsuite>");
+        let _ = writeln!(s, "</testsuites>");
+        s
+    }
+}
+
+/// Parses the test harness output line by line into per-test results
+#[derive(Debug)]
+pub struct TestHarness {
+    patterns: Patterns,
+    tests: Vec<TestCase>,
+    running: Option<(String, Instant)>,
+    complete: bool,
+    aborted: bool,
+}
+
+impl TestHarness {
+    pub fn new(cfg: &TestConfig) -> Result<Self, HarnessError> {
+        Ok(TestHarness {
+            patterns: Patterns::new(cfg)?,
+            tests: Vec::new(),
+            running: None,
+            complete: false,
+            aborted: false,
+        })
+    }
+
+    /// Whether the test run is over, either completed or aborted by a failure
+    pub fn done(&self) -> bool {
+        self.complete || self.aborted
+    }
+
+    pub fn line(&mut self, line: &str, at: Instant) {
+        if self.done() {
+            return;
+        }
+
+        if let Some(caps) = self.patterns.start.as_ref().and_then(|p| p.captures(line)) {
+            // The previous test passed if it didn't report otherwise
+            self.finish(None, TestStatus::Passed, None, at);
+            let name = caps
+                .name("name")
+                .map(|m| m.as_str().to_owned())
+                .unwrap_or_else(|| format!("test-{}", self.tests.len()));
+            self.running = Some((name, at));
+        }
+
+        if let Some(caps) = self.patterns.fail.captures(line) {
+            let name = caps.name("name").map(|m| m.as_str());
+            let message = caps
+                .name("message")
+                .map(|m| m.as_str().trim().to_owned())
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| line.trim().to_owned());
+            self.finish(name, TestStatus::Failed, Some(message), at);
+            if name.is_none() {
+                self.aborted = true;
+            }
+        } else if let Some(caps) = self.patterns.pass.as_ref().and_then(|p| p.captures(line)) {
+            let name = caps.name("name").map(|m| m.as_str());
+            self.finish(name, TestStatus::Passed, None, at);
+        } else if self.patterns.end.is_match(line) {
+            self.finish(None, TestStatus::Passed, None, at);
+            self.complete = true;
+        }
+    }
+
+    /// Consumes the harness, failing any test that was still running
+    pub fn report(mut self, name: &str, at: Instant) -> TestReport {
+        self.finish(
+            None,
+            TestStatus::Failed,
+            Some("The test did not complete".to_owned()),
+            at,
+        );
+        TestReport {
+            name: name.to_owned(),
+            complete: self.complete,
+            tests: self.tests,
+        }
+    }
+
+    fn finish(
+        &mut self,
+        name: Option<&str>,
+        status: TestStatus,
+        message: Option<String>,
+        at: Instant,
+    ) {
+        let Some((running_name, started)) = self.running.take() else {
+            // Results for tests that were never reported as started
+            if let Some(name) = name {
+                self.tests.push(TestCase {
+                    name: name.to_owned(),
+                    status,
+                    duration: None,
+                    message,
+                });
+            }
+            return;
+        };
+        self.tests.push(TestCase {
+            name: name.map(str::to_owned).unwrap_or(running_name),
+            status,
+            duration: Some(at.saturating_duration_since(started)),
+            message,
+        });
+    }
+}
+
+/// Passes output through to another writer while sending each complete line
+/// and the time it was received to the test harness
+pub struct LineTap {
+    inner: Box<dyn Write + Send>,
+    line: Vec<u8>,
+    tx: Sender<(String, Instant)>,
+}
+
+impl LineTap {
+    pub fn new(inner: Box<dyn Write + Send>, tx: Sender<(String, Instant)>) -> Self {
+        LineTap {
+            inner,
+            line: Vec::new(),
+            tx,
+        }
+    }
+}
+
+impl Write for LineTap {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        for b in buf[..n].iter() {
+            if *b == b'\n' {
+                let line = String::from_utf8_lossy(&self.line)
+                    .trim_end_matches('\r')
+                    .to_owned();
+                self.line.clear();
+                // The harness is gone once the test run is over
+                let _ = self.tx.send((line, Instant::now()));
+            } else {
+                self.line.push(*b);
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn serialize_secs<S: serde::Serializer>(d: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match d {
+        Some(d) => s.serialize_f64(d.as_secs_f64()),
+        None => s.serialize_none(),
+    }
+}
+
+/// The JUnit `time` attribute, left out when the duration isn't known
+fn time_attr(d: Option<Duration>) -> String {
+    d.map(|d| format!(r#" time="{:.3}""#, d.as_secs_f64()))
+        .unwrap_or_default()
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(format: TestFormat, lines: &[&str]) -> TestReport {
+        let cfg = TestConfig {
+            format,
+            ..Default::default()
+        };
+        let mut h = TestHarness::new(&cfg).unwrap();
+        let t0 = Instant::now();
+        for (i, l) in lines.iter().enumerate() {
+            h.line(l, t0 + Duration::from_millis(10 * i as u64));
+        }
+        h.report("suite", t0 + Duration::from_millis(10 * lines.len() as u64))
+    }
+
+    #[test]
+    fn defmt_test_output() {
+        let r = run(
+            TestFormat::DefmtTest,
+            &[
+                "(1/2) running `assert_true`...",
+                "(2/2) running `assert_eq`...",
+                "all tests passed!",
+            ],
+        );
+        assert!(r.success());
+        assert_eq!(r.passed(), 2);
+        assert_eq!(r.tests[0].name, "assert_true");
+        assert_eq!(r.tests[0].duration, Some(Duration::from_millis(10)));
+
+        let r = run(
+            TestFormat::DefmtTest,
+            &[
+                "(1/3) running `assert_true`...",
+                "(2/3) running `assert_false`...",
+                "ERROR panicked at 'assertion failed: false'",
+                "(3/3) running `never`...",
+            ],
+        );
+        assert!(!r.success());
+        assert_eq!(r.passed(), 1);
+        assert_eq!(r.failed(), 1);
+        assert_eq!(r.tests[1].name, "assert_false");
+        assert_eq!(
+            r.tests[1].message.as_deref(),
+            Some("panicked at 'assertion failed: false'")
+        );
+    }
+
+    #[test]
+    fn libtest_output() {
+        let r = run(
+            TestFormat::Libtest,
+            &[
+                "running 2 tests",
+                "test a::works ... ok",
+                "test a::broken ... FAILED",
+                "test result: FAILED. 1 passed; 1 failed",
+            ],
+        );
+        assert!(r.complete);
+        assert!(!r.success());
+        assert_eq!(r.tests.len(), 2);
+        assert_eq!(r.tests[0].status, TestStatus::Passed);
+        assert_eq!(r.tests[1].name, "a::broken");
+        assert_eq!(r.tests[1].status, TestStatus::Failed);
+        // libtest doesn't report when each test started
+        assert_eq!(r.tests[0].duration, None);
+        assert!(!r.to_junit().contains("time="));
+        assert!(!r.to_json().contains("duration"));
+    }
+
+    #[test]
+    fn incomplete_run() {
+        let r = run(TestFormat::DefmtTest, &["(1/1) running `hangs`..."]);
+        assert!(!r.complete);
+        assert_eq!(r.failed(), 1);
+        assert_eq!(
+            r.tests[0].message.as_deref(),
+            Some("The test did not complete")
+        );
+    }
+
+    #[test]
+    fn junit() {
+        let r = TestReport {
+            name: "suite".to_owned(),
+            complete: true,
+            tests: vec![TestCase {
+                name: "a<b>".to_owned(),
+                status: TestStatus::Failed,
+                duration: Some(Duration::from_millis(1500)),
+                message: Some("\"oops\"".to_owned()),
+            }],
+        };
+        let xml = r.to_junit();
+        assert!(xml.contains(r#"<testcase name="a&lt;b&gt;" classname="suite" time="1.500">"#));
+        assert!(xml.contains(r#"<failure message="&quot;oops&quot;"/>"#));
+    }
+}
@@ -0,0 +1,120 @@
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+const START_TASK_LABEL: &str = "renode-run: start renode";
+
+/// Renode emits this once the GDB server is ready for the debugger to attach
+const GDB_SERVER_READY_PATTERN: &str = "GDB server .* started on port";
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, clap::ValueEnum)]
+pub enum Format {
+    /// VS Code launch.json and tasks.json
+    Vscode,
+    /// Editor-agnostic JSON, written to stdout
+    Json,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, clap::ValueEnum)]
+pub enum Debugger {
+    /// The cortex-debug extension
+    CortexDebug,
+    /// The native C/C++ extension debugger (cppdbg)
+    Native,
+}
+
+/// Everything an editor needs to launch renode and attach a debugger to it
+#[derive(Clone, Debug)]
+pub struct DebugSession {
+    pub name: String,
+    pub elf: PathBuf,
+    pub resc: PathBuf,
+    pub output_dir: PathBuf,
+    pub renode: PathBuf,
+    pub renode_args: Vec<String>,
+    pub gdb: PathBuf,
+    pub gdb_port: u16,
+    pub break_on_main: bool,
+}
+
+impl DebugSession {
+    /// VS Code `launch.json` content
+    pub fn vscode_launch(&self, debugger: Debugger) -> Value {
+        let gdb_target = format!("localhost:{}", self.gdb_port);
+        let configuration = match debugger {
+            Debugger::CortexDebug => {
+                let mut c = json!({
+                    "name": self.name,
+                    "type": "cortex-debug",
+                    "request": "attach",
+                    "servertype": "external",
+                    "gdbTarget": gdb_target,
+                    "gdbPath": self.gdb,
+                    "executable": self.elf,
+                    "cwd": "${workspaceFolder}",
+                    "preLaunchTask": START_TASK_LABEL,
+                });
+                if self.break_on_main {
+                    c["runToEntryPoint"] = json!("main");
+                }
+                c
+            }
+            Debugger::Native => json!({
+                "name": self.name,
+                "type": "cppdbg",
+                "request": "launch",
+                "program": self.elf,
+                "cwd": "${workspaceFolder}",
+                "MIMode": "gdb",
+                "miDebuggerPath": self.gdb,
+                "miDebuggerServerAddress": gdb_target,
+                "stopAtEntry": self.break_on_main,
+                "preLaunchTask": START_TASK_LABEL,
+            }),
+        };
+        json!({
+            "version": "0.2.0",
+            "configurations": [configuration],
+        })
+    }
+
+    /// VS Code `tasks.json` content, a background task that runs the generated script
+    pub fn vscode_tasks(&self) -> Value {
+        let mut args = vec![self.resc.display().to_string()];
+        args.extend(self.renode_args.iter().cloned());
+        json!({
+            "version": "2.0.0",
+            "tasks": [{
+                "label": START_TASK_LABEL,
+                "type": "process",
+                "command": self.renode,
+                "args": args,
+                "isBackground": true,
+                "problemMatcher": {
+                    "owner": "renode-run",
+                    "pattern": { "regexp": "^__renode-run-no-problems__$" },
+                    "background": {
+                        "activeBegins": true,
+                        "beginsPattern": ".",
+                        "endsPattern": GDB_SERVER_READY_PATTERN,
+                    },
+                },
+            }],
+        })
+    }
+
+    /// Editor-agnostic description of the debug session
+    pub fn generic(&self) -> Value {
+        json!({
+            "name": self.name,
+            "elf": self.elf,
+            "resc": self.resc,
+            "output-dir": self.output_dir,
+            "renode": self.renode,
+            "renode-args": self.renode_args,
+            "gdb": self.gdb,
+            "gdb-port": self.gdb_port,
+            "gdb-target": format!("localhost:{}", self.gdb_port),
+            "break-on-main": self.break_on_main,
+        })
+    }
+}
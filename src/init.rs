@@ -0,0 +1,386 @@
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+const RUNNER_TARGET: &str = r#"[target.'cfg(all(target_arch = "arm", target_os = "none"))']"#;
+const RUNNER: &str = r#"runner = "renode-run""#;
+const METADATA_HEADER: &str = "[package.metadata.renode]";
+
+/// Boards known to work out of the box, with the UART their examples print to
+const PRESETS: &[(&str, &str)] = &[
+    ("stm32f4_discovery-kit", "sysbus.usart2"),
+    ("stm32f072b_discovery", "sysbus.usart1"),
+    ("stm32f7_discovery-bb", "sysbus.usart1"),
+    ("nucleo_h753zi", "sysbus.usart3"),
+    ("nrf52840dk_nrf52840", "sysbus.uart0"),
+    ("arduino_nano_33_ble", "sysbus.uart0"),
+];
+
+/// HAL, PAC and framework crates, a prefix of the chip feature they're built with (empty for any)
+/// and the board of that chip
+const HAL_BOARDS: &[(&str, &str, &str)] = &[
+    ("stm32f4xx-hal", "stm32f407", "stm32f4_discovery-kit"),
+    ("stm32f4", "stm32f407", "stm32f4_discovery-kit"),
+    ("embassy-stm32", "stm32f407", "stm32f4_discovery-kit"),
+    ("stm32f0xx-hal", "stm32f072", "stm32f072b_discovery"),
+    ("stm32f0", "stm32f0x2", "stm32f072b_discovery"),
+    ("embassy-stm32", "stm32f072", "stm32f072b_discovery"),
+    ("stm32f7xx-hal", "stm32f746", "stm32f7_discovery-bb"),
+    ("stm32f7", "stm32f7x6", "stm32f7_discovery-bb"),
+    ("embassy-stm32", "stm32f746", "stm32f7_discovery-bb"),
+    ("stm32h7xx-hal", "stm32h753", "nucleo_h753zi"),
+    ("stm32h7", "stm32h753", "nucleo_h753zi"),
+    ("embassy-stm32", "stm32h753", "nucleo_h753zi"),
+    ("nrf52840-hal", "", "nrf52840dk_nrf52840"),
+    ("nrf52840-pac", "", "nrf52840dk_nrf52840"),
+    ("embassy-nrf", "nrf52840", "nrf52840dk_nrf52840"),
+];
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum InitError {
+    #[error("'{_0}' doesn't have a [package] section")]
+    NoPackage(String),
+    #[error("'{_0}' already has a [package.metadata.renode] section")]
+    MetadataExists(String),
+    #[error("'{_0}' already sets the runner to '{_1}', use --force to replace it")]
+    RunnerExists(String, String),
+    #[error("Unknown board '{_0}', use --list to show the available boards")]
+    UnknownBoard(String),
+    #[error("No board was selected, use --board to pick one")]
+    NoBoard,
+    #[error("Init I/O error on '{_0}'. {_1}")]
+    Io(String, String),
+}
+
+/// A platform description to start the configuration with
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Board {
+    pub name: String,
+    pub platform: String,
+    /// The UART suggested for the terminal
+    pub uart: Option<String>,
+}
+
+impl Board {
+    pub fn new(name: &str) -> Self {
+        Board {
+            name: name.to_owned(),
+            platform: format!("@platforms/boards/{name}.repl"),
+            uart: PRESETS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, uart)| (*uart).to_owned()),
+        }
+    }
+}
+
+/// The boards of the renode installation, or the presets when it can't be found
+pub fn boards(renode_root: Option<&Path>) -> Vec<Board> {
+    let installed =
+        renode_root.and_then(|root| fs::read_dir(root.join("platforms").join("boards")).ok());
+    let mut names: Vec<String> = match installed {
+        Some(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "repl"))
+            .filter_map(|p| Some(p.file_stem()?.to_str()?.to_owned()))
+            .collect(),
+        None => Vec::new(),
+    };
+    if names.is_empty() {
+        names = PRESETS.iter().map(|(n, _)| (*n).to_owned()).collect();
+    }
+    names.sort();
+    names.iter().map(|n| Board::new(n)).collect()
+}
+
+/// A board matching the crate's dependencies
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct InferredBoard {
+    pub board: Board,
+    /// The dependency it was inferred from, e.g. `stm32f4xx-hal` with the feature `stm32f407`
+    pub reason: String,
+}
+
+/// Infers the board from the crate's dependencies, given by name and enabled features
+pub fn infer_board<'a>(
+    dependencies: impl IntoIterator<Item = (&'a str, &'a [String])>,
+) -> Option<InferredBoard> {
+    dependencies.into_iter().find_map(|(name, features)| {
+        HAL_BOARDS
+            .iter()
+            .filter(|(dep, _, _)| *dep == name)
+            .find_map(|(_, chip, board)| {
+                let reason = if chip.is_empty() {
+                    format!("the dependency '{name}'")
+                } else {
+                    let feature = features.iter().find(|f| f.starts_with(chip))?;
+                    format!("the dependency '{name}' with the feature '{feature}'")
+                };
+                Some(InferredBoard {
+                    board: Board::new(board),
+                    reason,
+                })
+            })
+    })
+}
+
+/// Finds a board by name, a `.repl` path is used as is
+pub fn find_board(boards: &[Board], board: &str) -> Result<Board, InitError> {
+    if board.ends_with(".repl") {
+        return Ok(Board {
+            name: board.to_owned(),
+            platform: board.to_owned(),
+            uart: None,
+        });
+    }
+    boards
+        .iter()
+        .find(|b| b.name == board)
+        .cloned()
+        .ok_or_else(|| InitError::UnknownBoard(board.to_owned()))
+}
+
+/// Asks for a board by number or name, the suggested board is picked by default
+/// and when there's no terminal to ask on
+pub fn pick_board(boards: &[Board], suggested: Option<&Board>) -> Result<Board, InitError> {
+    if !io::stdin().is_terminal() {
+        return suggested.cloned().ok_or(InitError::NoBoard);
+    }
+    for (i, b) in boards.iter().enumerate() {
+        println!("{:>4}. {}", i + 1, b.name);
+    }
+    let question = match suggested {
+        Some(b) => format!("Board [1-{}, empty for {}]: ", boards.len(), b.name),
+        None => format!("Board [1-{}]: ", boards.len()),
+    };
+    loop {
+        let answer = prompt(&question)?;
+        if answer.is_empty() {
+            return suggested.cloned().ok_or(InitError::NoBoard);
+        }
+        let picked = match answer.parse::<usize>() {
+            Ok(i) if (1..=boards.len()).contains(&i) => Ok(boards[i - 1].clone()),
+            _ => find_board(boards, &answer),
+        };
+        match picked {
+            Ok(b) => return Ok(b),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+}
+
+/// Asks a question, returning the trimmed answer
+pub fn prompt(question: &str) -> Result<String, InitError> {
+    print!("{question}");
+    io::stdout().flush().map_err(|e| io_err("stdout", e))?;
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| io_err("stdin", e))?;
+    Ok(answer.trim().to_owned())
+}
+
+/// The starter `[package.metadata.renode]` block
+pub fn metadata_block(package_name: &str, board: &Board, uart: Option<&str>, gdb: bool) -> String {
+    let mut block = format!(
+        "\n{METADATA_HEADER}\nname = '{package_name}'\nmachine-name = '{package_name}'\nplatform-descriptions = [\n    '{}',\n]\n",
+        board.platform
+    );
+    if let Some(uart) = uart {
+        block.push_str(&format!(
+            "socket-terminals = [\n    {{ uart = '{uart}', port = 'auto' }},\n]\n"
+        ));
+    }
+    if gdb {
+        block.push_str(
+            "\n[package.metadata.renode.gdb]\nport = 3333\nautostart-emulation = true\nbreak-on-main = true\n",
+        );
+    }
+    block
+}
+
+/// The manifest with the metadata block added
+pub fn manifest_with_metadata(
+    manifest_path: &Path,
+    board: &Board,
+    uart: Option<&str>,
+    gdb: bool,
+) -> Result<String, InitError> {
+    let path = manifest_path.display().to_string();
+    let content = fs::read_to_string(manifest_path).map_err(|e| io_err(&path, e))?;
+    let manifest = cargo_toml::Manifest::from_str(&content)
+        .map_err(|e| InitError::Io(path.clone(), e.to_string()))?;
+    let package = manifest
+        .package
+        .ok_or_else(|| InitError::NoPackage(path.clone()))?;
+    if content.lines().any(|l| l.trim() == METADATA_HEADER) {
+        return Err(InitError::MetadataExists(path));
+    }
+
+    let mut content = content;
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&metadata_block(package.name(), board, uart, gdb));
+    Ok(content)
+}
+
+/// The cargo config with renode-run set as the runner, None if it already is
+pub fn config_with_runner(config_path: &Path, force: bool) -> Result<Option<String>, InitError> {
+    let path = config_path.display().to_string();
+    let existing = if config_path.exists() {
+        Some(fs::read_to_string(config_path).map_err(|e| io_err(&path, e))?)
+    } else {
+        None
+    };
+    with_runner(existing.as_deref(), force).map_err(|runner| InitError::RunnerExists(path, runner))
+}
+
+/// Writes an edited file, creating its directory
+pub fn write(path: &Path, content: &str) -> Result<(), InitError> {
+    let p = path.display().to_string();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| io_err(&p, e))?;
+    }
+    fs::write(path, content).map_err(|e| io_err(&p, e))
+}
+
+/// The cargo config with the runner set, None if it's already set,
+/// or the existing runner when it can't be replaced.
+/// The runner of any `[target.*]` section counts, e.g. `[target.thumbv7em-none-eabihf]`.
+fn with_runner(existing: Option<&str>, force: bool) -> Result<Option<String>, String> {
+    let Some(existing) = existing else {
+        return Ok(Some(format!("{RUNNER_TARGET}\n{RUNNER}\n")));
+    };
+
+    let mut lines: Vec<&str> = existing.lines().collect();
+    let mut in_target = false;
+    let runners: Vec<usize> = (0..lines.len())
+        .filter(|i| {
+            let line = lines[*i].trim_start();
+            if line.starts_with('[') {
+                in_target = line.starts_with("[target.");
+                return false;
+            }
+            in_target
+                && line
+                    .strip_prefix("runner")
+                    .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .collect();
+    let others: Vec<usize> = runners
+        .iter()
+        .copied()
+        .filter(|i| !lines[*i].contains("renode-run"))
+        .collect();
+    if !runners.is_empty() {
+        return match others.first() {
+            None => Ok(None),
+            Some(i) if !force => Err(lines[*i].trim().to_owned()),
+            Some(_) => {
+                for i in others {
+                    lines[i] = RUNNER;
+                }
+                Ok(Some(lines.join("\n") + "\n"))
+            }
+        };
+    }
+
+    let Some(header) = lines.iter().position(|l| l.trim() == RUNNER_TARGET) else {
+        let sep = if existing.is_empty() || existing.ends_with("\n\n") {
+            ""
+        } else if existing.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        return Ok(Some(format!("{existing}{sep}{RUNNER_TARGET}\n{RUNNER}\n")));
+    };
+
+    lines.insert(header + 1, RUNNER);
+    Ok(Some(lines.join("\n") + "\n"))
+}
+
+fn io_err(path: &str, e: io::Error) -> InitError {
+    InitError::Io(path.to_owned(), e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runner_edits() {
+        let created = with_runner(None, false).unwrap().unwrap();
+        assert_eq!(created, format!("{RUNNER_TARGET}\n{RUNNER}\n"));
+        assert_eq!(with_runner(Some(&created), false), Ok(None));
+
+        let other = "[build]\ntarget = \"thumbv7em-none-eabihf\"\n";
+        assert_eq!(
+            with_runner(Some(other), false).unwrap().unwrap(),
+            format!("{other}\n{RUNNER_TARGET}\n{RUNNER}\n")
+        );
+
+        let probe_rs = format!(
+            "{RUNNER_TARGET}\nrunner = \"probe-rs run\"\nrustflags = []\n\n[build]\ntarget = \"thumbv7em-none-eabihf\"\n"
+        );
+        assert_eq!(
+            with_runner(Some(&probe_rs), false),
+            Err("runner = \"probe-rs run\"".to_owned())
+        );
+        assert_eq!(
+            with_runner(Some(&probe_rs), true).unwrap().unwrap(),
+            probe_rs.replace("probe-rs run", "renode-run")
+        );
+
+        let no_runner = format!("{RUNNER_TARGET}\nrustflags = []\n");
+        assert_eq!(
+            with_runner(Some(&no_runner), false).unwrap().unwrap(),
+            format!("{RUNNER_TARGET}\n{RUNNER}\nrustflags = []\n")
+        );
+
+        let triple = "[target.thumbv7em-none-eabihf]\nrunner = 'probe-rs run --chip STM32F407VG'\n";
+        assert_eq!(
+            with_runner(Some(triple), false),
+            Err("runner = 'probe-rs run --chip STM32F407VG'".to_owned())
+        );
+        assert_eq!(
+            with_runner(Some(triple), true).unwrap().unwrap(),
+            format!("[target.thumbv7em-none-eabihf]\n{RUNNER}\n")
+        );
+        let build_runner = "[build]\nrunner = \"x\"\n";
+        assert_eq!(
+            with_runner(Some(build_runner), false).unwrap().unwrap(),
+            format!("{build_runner}\n{RUNNER_TARGET}\n{RUNNER}\n")
+        );
+    }
+
+    #[test]
+    fn inferred_boards() {
+        let none: &[String] = &[];
+        let f407 = ["rt".to_owned(), "stm32f407".to_owned()];
+        let inferred = infer_board([("cortex-m", none), ("stm32f4xx-hal", &f407[..])]).unwrap();
+        assert_eq!(inferred.board.name, "stm32f4_discovery-kit");
+        assert_eq!(
+            inferred.reason,
+            "the dependency 'stm32f4xx-hal' with the feature 'stm32f407'"
+        );
+
+        let h753 = ["stm32h753zi".to_owned(), "time-driver-any".to_owned()];
+        assert_eq!(
+            infer_board([("embassy-stm32", &h753[..])]).map(|i| i.board.platform),
+            Some("@platforms/boards/nucleo_h753zi.repl".to_owned())
+        );
+        assert_eq!(
+            infer_board([("nrf52840-hal", none)]).map(|i| i.board.uart),
+            Some(Some("sysbus.uart0".to_owned()))
+        );
+
+        let f411 = ["stm32f411".to_owned()];
+        assert_eq!(infer_board([("stm32f4xx-hal", &f411[..])]), None);
+    }
+}
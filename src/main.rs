@@ -1,20 +1,70 @@
-use crate::config::RenodeRunConfig;
-use crate::opts::Opts;
+use crate::assets::AssetError;
+use crate::config::{Backend, GdbConfig, PortConfig, RenodeRunConfig, TestSource};
+use crate::console::{Console, ConsoleError};
+use crate::container::{Container, ContainerError};
+use crate::doctor::{Check, Report};
+use crate::elf::ElfFile;
+use crate::harness::{HarnessError, LineTap, TestHarness, TestStatus};
+use crate::ide::DebugSession;
+use crate::init::{InferredBoard, InitError};
+use crate::monitor::{MonitorClient, MonitorError};
+use crate::opts::{
+    Command, DoctorOpts, GdbOpts, IdeOpts, InitOpts, Opts, PlatformsOpts, RobotOpts, TestOpts,
+    ToolchainCommand, ToolchainOpts, WatchOpts,
+};
+use crate::platforms::PlatformsError;
+use crate::renode::{Feature, RenodeError};
 use crate::resc_gen::RescGen;
-use crate::types::{PlatformDescriptionKind, RescDefinition};
+use crate::robot::{Expectation, RobotError};
+use crate::rtt::{RttError, RttReader};
+use crate::tail::FileTail;
+use crate::timeout::WaitOutcome;
+use crate::toolchain::Toolchains;
+use crate::types::RescDefinition;
+use crate::watch::{MtimeWatcher, WatchError};
 use clap::Parser;
 use serde::Deserialize;
 use std::{
-    env, fs,
-    path::PathBuf,
-    process::{Command, Stdio},
+    env, fmt,
+    fs::{self, File},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
 };
 
+mod assets;
 mod config;
+mod console;
+mod container;
+mod doctor;
+mod elf;
 mod envsub;
+mod gdb;
+mod harness;
+mod ide;
+mod init;
+mod memory;
+mod monitor;
 mod opts;
+mod platforms;
+mod ports;
+mod renode;
+mod repl;
 mod resc_gen;
+mod robot;
+mod rtt;
+mod semihosting;
+mod tail;
+mod timeout;
+mod toolchain;
 mod types;
+mod watch;
+
+/// How long to wait for the monitor to connect and accept the quit command when quitting renode
+const MONITOR_QUIT_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Clone, Debug, Deserialize, Default)]
 struct CargoPackageMetadata {
@@ -27,45 +77,1327 @@ fn main() {
 
     env_logger::init();
 
-    let input_file = if let Some(manual_input_file) = opts.config.as_ref() {
+    // Toolchains aren't specific to a project
+    if let Some(Command::Toolchain(toolchain_opts)) = opts.command.as_ref() {
+        process::exit(toolchain_main(toolchain_opts));
+    }
+    // Doctor reports configuration errors instead of failing on them
+    if let Some(Command::Doctor(doctor_opts)) = opts.command.as_ref() {
+        process::exit(doctor_main(doctor_opts, &opts));
+    }
+    // Init creates the config
+    if let Some(Command::Init(init_opts)) = opts.command.as_ref() {
+        process::exit(init_main(init_opts, &opts));
+    }
+    if let Some(Command::Platforms(platforms_opts)) = opts.command.as_ref() {
+        process::exit(platforms_main(platforms_opts, &opts));
+    }
+
+    let renode_config = load_config(&opts);
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let output_dir = opts.output_dir.clone().unwrap_or_else(|| {
+        if let Some(Command::Ide(_)) = opts.command {
+            // Editors run the generated script later, so it can't be temporary
+            PathBuf::from("target").join("renode-run")
+        } else {
+            tmpdir.path().join("renode-run")
+        }
+    });
+
+    log::debug!("Using output dir '{}'", output_dir.display());
+    fs::create_dir_all(&output_dir).unwrap();
+    set_asset_environment(&renode_config, &output_dir).unwrap_or_else(|e| exit_with_error(e));
+
+    let exit_code = match opts.command.as_ref() {
+        Some(Command::Gdb(gdb_opts)) => gdb_main(gdb_opts, &renode_config, &output_dir),
+        Some(Command::Ide(ide_opts)) => ide_main(ide_opts, &opts, renode_config, &output_dir),
+        Some(Command::Test(test_opts)) => test_main(test_opts, &opts, renode_config, &output_dir),
+        Some(Command::Robot(robot_opts)) => {
+            robot_main(robot_opts, &opts, renode_config, &output_dir)
+        }
+        Some(Command::Watch(watch_opts)) => {
+            watch_main(watch_opts, &opts, renode_config, &output_dir)
+        }
+        Some(Command::Toolchain(_))
+        | Some(Command::Doctor(_))
+        | Some(Command::Init(_))
+        | Some(Command::Platforms(_)) => {
+            unreachable!("Commands without a project ELF are handled first")
+        }
+        None => run(&opts, renode_config, &output_dir),
+    };
+
+    drop(tmpdir);
+    process::exit(exit_code);
+}
+
+fn load_config(opts: &Opts) -> RenodeRunConfig {
+    let (input_file, metadata) = if let Some(manual_input_file) = opts.config.as_ref() {
         log::debug!("Using config '{}'", manual_input_file.display());
-        manual_input_file.clone()
+        (manual_input_file.clone(), None)
     } else {
         log::debug!("Looking up default config from cargo metadata");
         let cmd = cargo_metadata::MetadataCommand::new();
-        let _metadata = cmd.exec().unwrap();
+        let metadata = cmd.exec().unwrap();
 
         // TODO deal with workspaces/selected-package-from-available-ones ...
 
-        PathBuf::from("Cargo.toml")
+        (PathBuf::from("Cargo.toml"), Some(metadata))
     };
 
-    let manifest_bytes = fs::read(input_file).unwrap();
+    let mut renode_config = parse_config(&input_file).unwrap();
+    if !has_platform(&renode_config) {
+        if let Some(inferred) = infer_board(&input_file, metadata) {
+            eprintln!(
+                "renode-run: no platform descriptions are configured, using '{}' for {}",
+                inferred.board.platform, inferred.reason
+            );
+            renode_config
+                .resc
+                .platform_descriptions
+                .push(inferred.board.platform);
+        }
+    }
+    set_environment(opts, &renode_config);
+    renode_config
+}
+
+fn has_platform(renode_config: &RenodeRunConfig) -> bool {
+    renode_config.resc.platform_description.is_some()
+        || !renode_config.resc.platform_descriptions.is_empty()
+}
+
+/// Infers the board from the dependencies of the manifest's package
+fn infer_board(
+    manifest_path: &Path,
+    metadata: Option<cargo_metadata::Metadata>,
+) -> Option<InferredBoard> {
+    let metadata = match metadata {
+        Some(m) => m,
+        None => cargo_metadata::MetadataCommand::new()
+            .manifest_path(manifest_path)
+            .no_deps()
+            .exec()
+            .ok()?,
+    };
+    let manifest_path = manifest_path.canonicalize().ok()?;
+    let package = metadata.packages.iter().find(|p| {
+        p.manifest_path.as_std_path().canonicalize().ok() == Some(manifest_path.clone())
+    })?;
+    init::infer_board(
+        package
+            .dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.features.as_slice())),
+    )
+}
+
+fn parse_config(input_file: &Path) -> Result<RenodeRunConfig, cargo_toml::Error> {
+    let manifest_bytes = fs::read(input_file).map_err(cargo_toml::Error::Io)?;
 
     let manifest =
-        cargo_toml::Manifest::<CargoPackageMetadata>::from_slice_with_metadata(&manifest_bytes)
-            .unwrap();
+        cargo_toml::Manifest::<CargoPackageMetadata>::from_slice_with_metadata(&manifest_bytes)?;
 
-    let renode_config = manifest
+    Ok(manifest
         .package
         .and_then(|p| p.metadata)
         .and_then(|md| md.renode)
-        .unwrap_or_default();
+        .unwrap_or_default())
+}
 
+/// Exposes the configured environment variables and the ELF to substitutions and renode
+fn set_environment(opts: &Opts, renode_config: &RenodeRunConfig) {
     for (env_var, env_val) in renode_config.app.environment_variables.iter() {
         env::set_var(env_var, env_val);
     }
+    if let Some(input) = opts.input() {
+        env::set_var("RENODE_RUN_ELF", input);
+    }
+}
+
+/// Exposes the generated paths of the assets to substitutions
+fn set_asset_environment(
+    renode_config: &RenodeRunConfig,
+    output_dir: &Path,
+) -> Result<usize, AssetError> {
+    let assets = assets::resolve(&renode_config.resc.assets)?;
+    for a in assets.iter() {
+        env::set_var(a.env_var(), a.output_path(output_dir));
+    }
+    Ok(assets.len())
+}
+
+fn toolchain_main(toolchain_opts: &ToolchainOpts) -> i32 {
+    let toolchains = Toolchains::open().unwrap_or_else(|e| exit_with_error(e));
+    match &toolchain_opts.command {
+        ToolchainCommand::Add(add_opts) => {
+            let t = toolchains
+                .add(&add_opts.archive, add_opts.force)
+                .unwrap_or_else(|e| exit_with_error(e));
+            println!("Installed renode {} in '{}'", t.version, t.root.display());
+            if toolchains.default_version().is_none() {
+                toolchains
+                    .set_default(&t.version.to_string())
+                    .unwrap_or_else(|e| exit_with_error(e));
+                println!("Using renode {} by default", t.version);
+            }
+        }
+        ToolchainCommand::List => {
+            let default = toolchains.default_version();
+            let installed = toolchains.list();
+            if installed.is_empty() {
+                println!(
+                    "No toolchains installed in '{}'",
+                    toolchains.dir().display()
+                );
+            }
+            for t in installed.iter() {
+                let marker = if Some(&t.version) == default.as_ref() {
+                    " (default)"
+                } else {
+                    ""
+                };
+                println!("{}{marker}\t{}", t.version, t.root.display());
+            }
+        }
+        ToolchainCommand::Default(default_opts) => {
+            let t = toolchains
+                .set_default(&default_opts.version)
+                .unwrap_or_else(|e| exit_with_error(e));
+            println!("Using renode {} by default", t.version);
+        }
+    }
+    0
+}
+
+fn init_main(init_opts: &InitOpts, opts: &Opts) -> i32 {
+    let manifest_path = opts
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml"));
+    let renode_root = find_renode_bin(opts, &RenodeRunConfig::default())
+        .ok()
+        .and_then(|bin| renode::root(&bin));
+    let boards = init::boards(renode_root.as_deref());
+
+    if init_opts.list {
+        for b in boards.iter() {
+            println!("{}\t{}", b.name, b.platform);
+        }
+        return 0;
+    }
+
+    // Ask for whatever wasn't given when the board is picked interactively
+    let interactive = init_opts.board.is_none() && io::stdin().is_terminal();
+    let board = match init_opts.board.as_ref() {
+        Some(b) => init::find_board(&boards, b),
+        None => {
+            let inferred = infer_board(&manifest_path, None);
+            if let Some(i) = inferred.as_ref() {
+                println!("Inferred the board '{}' from {}", i.board.name, i.reason);
+            }
+            init::pick_board(&boards, inferred.as_ref().map(|i| &i.board))
+        }
+    }
+    .unwrap_or_else(|e| exit_with_error(e));
+    let uart = match init_opts.uart.clone() {
+        Some(uart) => Some(uart),
+        None if interactive => {
+            let default = board.uart.clone().unwrap_or_default();
+            let answer = init::prompt(&format!("UART terminal, empty for none [{default}]: "))
+                .unwrap_or_else(|e| exit_with_error(e));
+            match answer.as_str() {
+                "" if default.is_empty() => None,
+                "" => Some(default),
+                "none" => None,
+                _ => Some(answer),
+            }
+        }
+        None => None,
+    };
+    let gdb = init_opts.gdb
+        || (interactive
+            && init::prompt("Add a GDB server config? [y/N]: ")
+                .unwrap_or_else(|e| exit_with_error(e))
+                .eq_ignore_ascii_case("y"));
+
+    // Both edits are made before anything is written, so a failure leaves the files untouched
+    let manifest = init::manifest_with_metadata(&manifest_path, &board, uart.as_deref(), gdb)
+        .unwrap_or_else(|e| exit_with_error(e));
+    let cargo_config_path = manifest_path
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(".cargo")
+        .join("config.toml");
+    let cargo_config = match init::config_with_runner(&cargo_config_path, init_opts.force) {
+        Ok(c) => c,
+        Err(e @ InitError::RunnerExists(..)) => {
+            eprintln!("renode-run: {e}");
+            return 1;
+        }
+        Err(e) => exit_with_error(e),
+    };
+
+    init::write(&manifest_path, &manifest).unwrap_or_else(|e| exit_with_error(e));
+    println!(
+        "Added [package.metadata.renode] for '{}' to '{}'",
+        board.name,
+        manifest_path.display()
+    );
+    match cargo_config {
+        Some(content) => {
+            init::write(&cargo_config_path, &content).unwrap_or_else(|e| exit_with_error(e));
+            println!(
+                "Set renode-run as the runner in '{}'",
+                cargo_config_path.display()
+            );
+        }
+        None => println!(
+            "renode-run is already the runner in '{}'",
+            cargo_config_path.display()
+        ),
+    }
+    println!("Run your firmware with 'cargo run', or check the setup with 'renode-run doctor'");
+    0
+}
+
+fn platforms_main(platforms_opts: &PlatformsOpts, opts: &Opts) -> i32 {
+    // The project's config can select the renode installation
+    let config_file = opts
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml"));
+    let renode_config = if config_file.exists() {
+        parse_config(&config_file).unwrap_or_else(|e| exit_with_error(e))
+    } else {
+        RenodeRunConfig::default()
+    };
+    set_environment(opts, &renode_config);
+    let renode_bin = renode_bin(opts, &renode_config);
+    let platforms = renode::root(&renode_bin)
+        .ok_or_else(|| PlatformsError::NoRoot(renode_bin.display().to_string()))
+        .and_then(|root| platforms::list(&root))
+        .unwrap_or_else(|e| exit_with_error(e));
+
+    let mut found = false;
+    for p in platforms.iter().filter(|p| {
+        platforms_opts
+            .query
+            .as_ref()
+            .map(|q| p.matches(q))
+            .unwrap_or(true)
+    }) {
+        found = true;
+        println!("{}", p.resc_path);
+        if !p.cpu_types.is_empty() || !p.cpu_models.is_empty() {
+            println!(
+                "    cpu: {} ({})",
+                p.cpu_types.join(", "),
+                p.cpu_models.join(", ")
+            );
+        }
+        if !p.peripherals.is_empty() {
+            println!("    peripherals: {}", p.peripherals.join(", "));
+        }
+    }
+    if found {
+        0
+    } else {
+        eprintln!("renode-run: no platforms found");
+        1
+    }
+}
+
+fn doctor_main(doctor_opts: &DoctorOpts, opts: &Opts) -> i32 {
+    let mut report = Report::default();
+    let config_file = opts
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml"));
+
+    let mut renode_config = match parse_config(&config_file) {
+        Ok(cfg) => {
+            report.push(Check::ok(
+                "config",
+                format!("'{}' is valid", config_file.display()),
+            ));
+            cfg
+        }
+        Err(e) => {
+            report.push(Check::error(
+                "config",
+                format!("'{}' is invalid. {e}", config_file.display()),
+                "fix [package.metadata.renode], or use --config to pick the manifest",
+            ));
+            println!("{report}");
+            return 1;
+        }
+    };
+    if !has_platform(&renode_config) {
+        if let Some(inferred) = infer_board(&config_file, None) {
+            report.push(Check::warning(
+                "platform",
+                format!(
+                    "none configured, using '{}' for {}",
+                    inferred.board.platform, inferred.reason
+                ),
+                "set 'platform-descriptions' to pick the board explicitly",
+            ));
+            renode_config
+                .resc
+                .platform_descriptions
+                .push(inferred.board.platform);
+        }
+    }
+    set_environment(opts, &renode_config);
+    report.push(doctor::env_vars(&config_file));
 
+    // The same default as the other commands, removed once the checks are done
     let tmpdir = tempfile::tempdir().unwrap();
     let output_dir = opts
         .output_dir
+        .clone()
         .unwrap_or_else(|| tmpdir.path().join("renode-run"));
+    report.push(match set_asset_environment(&renode_config, &output_dir) {
+        Ok(n) => Check::ok("assets", format!("{n} files")),
+        Err(e) => Check::error(
+            "assets",
+            e.to_string(),
+            "local paths are relative to the directory renode-run is run from",
+        ),
+    });
 
-    log::debug!("Using output dir '{}'", output_dir.display());
-    fs::create_dir_all(&output_dir).unwrap();
+    let mut renode_root = None;
+    match renode_config.app.backend {
+        Backend::Native => match find_renode_bin(opts, &renode_config) {
+            Ok(bin) => {
+                renode_root = renode::root(&bin);
+                report.push(doctor_renode(&bin, &renode_config, doctor_opts));
+            }
+            Err(e) => report.push(Check::error(
+                "renode",
+                e.to_string(),
+                "install renode, 'renode-run toolchain add' a portable release, or set RENODE_ROOT",
+            )),
+        },
+        Backend::Docker | Backend::Podman => {
+            let engine = format!("{:?}", renode_config.app.backend).to_lowercase();
+            if renode::find_in_path(Path::new(&engine)).is_none() {
+                report.push(Check::error(
+                    "renode",
+                    format!("the container engine '{engine}' could not be found"),
+                    format!("install {engine} or use 'backend = \"native\"'"),
+                ));
+            } else if renode_config.app.image.is_none() {
+                report.push(Check::error(
+                    "renode",
+                    ContainerError::MissingImage(engine).to_string(),
+                    "set 'image', e.g. \"antmicro/renode:1.15.3\"",
+                ));
+            } else {
+                report.push(Check::ok("renode", format!("runs in {engine}")));
+            }
+        }
+    }
+
+    report.extend(doctor::platform_descriptions(
+        &renode_config,
+        renode_root.as_deref(),
+    ));
+    if let Some(input) = doctor_opts.input.as_ref() {
+        let mut resolved_config = renode_config.clone();
+        let script = ports::resolve(&mut resolved_config)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                RescDefinition::new(&resolved_config.resc, &resolved_config.app, input)
+                    .map_err(|e| e.to_string())
+            });
+        match script {
+            Ok(resc_def) => {
+                report.push(Check::ok(
+                    "script",
+                    format!("generated for '{}'", input.display()),
+                ));
+                report.push(doctor::memory(
+                    &resc_def.platform_descriptions,
+                    renode_root.as_deref(),
+                    input,
+                    renode_config.app.allow_unmapped_memory,
+                ));
+            }
+            Err(e) => report.push(Check::error("script", e, "fix the reported config item")),
+        }
+    }
+    report.push(doctor::output_dir(&output_dir));
+    report.extend(doctor::ports(&renode_config));
+    report.push(doctor::gdb(&renode_config));
+    report.push(doctor::display(
+        &renode_config.cli,
+        renode_config.app.backend,
+    ));
+
+    println!("{report}");
+    if report.success() {
+        0
+    } else {
+        1
+    }
+}
+
+/// Reports the renode version, checked against renode-version and, with an ELF, the script's features
+fn doctor_renode(bin: &Path, renode_config: &RenodeRunConfig, doctor_opts: &DoctorOpts) -> Check {
+    let name = "renode";
+    let version = match renode::version(bin) {
+        Ok(v) => v,
+        Err(e) => {
+            return Check::error(
+                name,
+                e.to_string(),
+                "check that renode runs, e.g. its dependencies are installed",
+            )
+        }
+    };
+    let requirement = renode_config
+        .app
+        .renode_version
+        .as_ref()
+        .map(|r| envsub::envsub(r).map_err(RenodeError::from))
+        .transpose()
+        .and_then(|r| r.as_deref().map(renode::parse_requirement).transpose());
+    let features = doctor_opts
+        .input
+        .as_ref()
+        .and_then(|input| {
+            let mut cfg = renode_config.clone();
+            ports::resolve(&mut cfg).ok()?;
+            RescDefinition::new(&cfg.resc, &cfg.app, input).ok()
+        })
+        .map(|resc_def| Feature::used_by(&resc_def))
+        .unwrap_or_default();
+    let checked =
+        requirement.and_then(|req| renode::check_version(&version, req.as_ref(), &features));
+    match checked {
+        Ok(()) => Check::ok(name, format!("{version} at '{}'", bin.display())),
+        Err(e) => Check::error(
+            name,
+            e.to_string(),
+            "install a matching renode, e.g. with 'renode-run toolchain add', or update renode-version",
+        ),
+    }
+}
+
+fn gdb_main(gdb_opts: &GdbOpts, renode_config: &RenodeRunConfig, output_dir: &Path) -> i32 {
+    let gdb_config = renode_config.resc.gdb.clone().unwrap_or_default();
+    let gdb_bin = gdb_bin(gdb_opts.gdb_bin.as_ref(), &gdb_config);
+    let port = match (gdb_opts.port, gdb_config.port) {
+        (Some(p), _) | (None, PortConfig::Fixed(p)) => p,
+        (None, PortConfig::Auto) => {
+            eprintln!("The GDB port is \"auto\", use --port with the port renode-run printed");
+            return 1;
+        }
+    };
+
+    let init_script_path = output_dir.join(gdb::INIT_SCRIPT_FILE_NAME);
+    fs::write(
+        &init_script_path,
+        gdb::init_script(&gdb_opts.input, port, gdb_config.break_on_main),
+    )
+    .unwrap();
+
+    log::debug!("Using gdb bin '{}'", gdb_bin.display());
+    let status = process::Command::new(gdb_bin)
+        .arg("-x")
+        .arg(&init_script_path)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .status()
+        .expect("Failed to start gdb process");
+    status.code().unwrap_or(1)
+}
+
+fn ide_main(
+    ide_opts: &IdeOpts,
+    opts: &Opts,
+    mut renode_config: RenodeRunConfig,
+    output_dir: &Path,
+) -> i32 {
+    // The editor's launch task runs the renode binary
+    match renode_config.app.backend {
+        Backend::Native => (),
+        Backend::Docker => exit_with_error(ContainerError::Ide("docker".to_owned())),
+        Backend::Podman => exit_with_error(ContainerError::Ide("podman".to_owned())),
+    }
+    // Debugging requires the GDB server, and the firmware mustn't run before the debugger attaches
+    let gdb_config = renode_config
+        .resc
+        .gdb
+        .get_or_insert_with(GdbConfig::default);
+    gdb_config.autostart_emulation = true;
+    let gdb_config = gdb_config.clone();
+    resolve_ports(&mut renode_config);
+    // SAFETY: the ports were just resolved
+    let gdb_port = renode_config
+        .resc
+        .gdb
+        .as_ref()
+        .unwrap()
+        .port
+        .fixed()
+        .unwrap();
+    let resc_def =
+        RescDefinition::new(&renode_config.resc, &renode_config.app, &ide_opts.input).unwrap();
+    let resc_path = generate(opts, &resc_def, &renode_config, output_dir);
+
+    let session = DebugSession {
+        name: format!("Debug {} in renode", resc_def.name),
+        elf: fs::canonicalize(&ide_opts.input).unwrap(),
+        resc: fs::canonicalize(resc_path).unwrap(),
+        output_dir: fs::canonicalize(output_dir).unwrap(),
+        renode: renode_bin(opts, &renode_config),
+        renode_args: renode_config.cli.to_args(),
+        gdb: gdb_bin(None, &gdb_config),
+        gdb_port,
+        break_on_main: gdb_config.break_on_main,
+    };
+
+    match ide_opts.format {
+        ide::Format::Vscode => {
+            fs::create_dir_all(&ide_opts.vscode_dir).unwrap();
+            let files = [
+                ("launch.json", session.vscode_launch(ide_opts.debugger)),
+                ("tasks.json", session.vscode_tasks()),
+            ];
+            for (file_name, _) in files.iter() {
+                let path = ide_opts.vscode_dir.join(file_name);
+                if path.exists() && !ide_opts.force {
+                    eprintln!(
+                        "'{}' already exists, use --force to overwrite it",
+                        path.display()
+                    );
+                    return 1;
+                }
+            }
+            for (file_name, content) in files.iter() {
+                let path = ide_opts.vscode_dir.join(file_name);
+                log::debug!("Writing '{}'", path.display());
+                fs::write(path, serde_json::to_string_pretty(content).unwrap()).unwrap();
+            }
+        }
+        ide::Format::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&session.generic()).unwrap()
+            )
+        }
+    }
+
+    0
+}
+
+fn test_main(
+    test_opts: &TestOpts,
+    opts: &Opts,
+    mut renode_config: RenodeRunConfig,
+    output_dir: &Path,
+) -> i32 {
+    apply_overrides(opts, &mut renode_config);
+    resolve_ports(&mut renode_config);
+    let test_config = renode_config.app.test.clone().unwrap_or_default();
+    let mut harness = TestHarness::new(&test_config).unwrap();
+    let mut resc_def =
+        RescDefinition::new(&renode_config.resc, &renode_config.app, &test_opts.input).unwrap();
+
+    let source = match test_config.source {
+        TestSource::Uart => {
+            let uart = test_config
+                .uart
+                .as_ref()
+                .ok_or(HarnessError::MissingUart)
+                .unwrap();
+            let uart = envsub::envsub(uart.trim()).unwrap();
+            resc_def.uart_captures.push(uart.clone());
+            OutputSource::Uart(uart)
+        }
+        TestSource::Rtt => OutputSource::Rtt(test_config.rtt_channel),
+        TestSource::Semihosting => {
+            if resc_def.semihosting.is_none() {
+                exit_with_error(HarnessError::MissingSemihosting);
+            }
+            OutputSource::Semihosting
+        }
+    };
+
+    let script = generate(opts, &resc_def, &renode_config, output_dir);
+
+    let (tx, rx) = mpsc::channel();
+    let tap = OutputTap {
+        source,
+        sink: Box::new(LineTap::new(Box::new(io::stdout()), tx)),
+    };
+    let outcome = launch(
+        opts,
+        &renode_config,
+        &resc_def,
+        &script,
+        output_dir,
+        Some(tap),
+        || {
+            while let Ok((line, at)) = rx.try_recv() {
+                harness.line(&line, at);
+            }
+            harness.done()
+        },
+    );
+    // Lines flushed after renode exited
+    while let Ok((line, at)) = rx.try_recv() {
+        harness.line(&line, at);
+    }
+
+    let suite_name = test_opts
+        .input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| resc_def.name.to_string());
+    let report = harness.report(&suite_name, Instant::now());
+
+    println!();
+    for t in report.tests.iter() {
+        let status = match t.status {
+            TestStatus::Passed => "ok",
+            TestStatus::Failed => "FAILED",
+        };
+        match t.duration {
+            Some(d) => println!("test {} ... {status} ({:.3}s)", t.name, d.as_secs_f64()),
+            None => println!("test {} ... {status}", t.name),
+        }
+        if let Some(m) = t.message.as_ref() {
+            println!("    {m}");
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed{}",
+        if report.success() { "ok" } else { "FAILED" },
+        report.passed(),
+        report.failed(),
+        if report.complete { "" } else { "; incomplete" }
+    );
 
+    let junit = test_opts.junit.clone().or_else(|| {
+        test_config
+            .junit
+            .as_ref()
+            .map(|p| PathBuf::from(envsub::envsub(p).unwrap()))
+    });
+    if let Some(p) = junit {
+        log::debug!("Writing JUnit report '{}'", p.display());
+        fs::write(p, report.to_junit()).unwrap();
+    }
+    let json = test_opts.json.clone().or_else(|| {
+        test_config
+            .json
+            .as_ref()
+            .map(|p| PathBuf::from(envsub::envsub(p).unwrap()))
+    });
+    if let Some(p) = json {
+        log::debug!("Writing JSON report '{}'", p.display());
+        fs::write(p, report.to_json()).unwrap();
+    }
+
+    match outcome {
+        RunOutcome::WallTimeout(_) | RunOutcome::VirtualTimeout(_) => {
+            outcome.report();
+            timeout::EXIT_CODE
+        }
+        _ if report.success() => 0,
+        _ => 1,
+    }
+}
+
+fn robot_main(
+    robot_opts: &RobotOpts,
+    opts: &Opts,
+    mut renode_config: RenodeRunConfig,
+    output_dir: &Path,
+) -> i32 {
+    apply_overrides(opts, &mut renode_config);
+    resolve_ports(&mut renode_config);
+    // The suite starts the emulation once its terminal testers are created
+    renode_config.app.omit_start = true;
     let resc_def =
-        RescDefinition::new(&renode_config.resc, &renode_config.app, &opts.input).unwrap();
+        RescDefinition::new(&renode_config.resc, &renode_config.app, &robot_opts.input).unwrap();
+    if resc_def.virtual_timeout.is_some() {
+        exit_with_error(RobotError::VirtualTimeout);
+    }
+    let script = generate(opts, &resc_def, &renode_config, output_dir);
+
+    let expectations = renode_config
+        .app
+        .expect
+        .iter()
+        .map(Expectation::new)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let suite_path = output_dir.join(robot::SUITE_FILE_NAME);
+    log::debug!("Using output robot suite '{}'", suite_path.display());
+    fs::write(
+        &suite_path,
+        robot::suite(
+            &resc_def.name,
+            fs::canonicalize(&script).unwrap(),
+            resc_def.start.as_deref(),
+            &resc_def.post_start_commands,
+            &expectations,
+        )
+        .unwrap(),
+    )
+    .unwrap();
+
+    if robot_opts.no_run {
+        return 0;
+    }
+
+    let renode_test_bin = if let Some(opts_bin) = robot_opts.renode_test_bin.as_ref() {
+        opts_bin.clone()
+    } else if let Some(cfg_bin) = renode_config.app.renode_test.as_ref() {
+        PathBuf::from(envsub::envsub(cfg_bin).unwrap())
+    } else {
+        PathBuf::from("renode-test")
+    };
+    let results_dir = output_dir.join(robot::RESULTS_DIR_NAME);
+
+    log::debug!("Using renode-test bin '{}'", renode_test_bin.display());
+    let status = process::Command::new(renode_test_bin)
+        .arg(&suite_path)
+        .arg("--results-dir")
+        .arg(&results_dir)
+        .envs(renode_config.app.environment_variables.iter().cloned())
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .status()
+        .expect("Failed to start renode-test process");
+    if let Some(summary) = robot::summary(&results_dir) {
+        eprintln!("renode-run: robot {summary}");
+    }
+    eprintln!(
+        "renode-run: robot results written to '{}'",
+        results_dir.display()
+    );
+    status.code().unwrap_or(1)
+}
+
+/// Runs renode until interrupted, rebuilding on source changes. A new ELF is reloaded into
+/// the running emulation over the monitor, changes to the config or local platform
+/// descriptions regenerate the script and restart renode.
+fn watch_main(
+    watch_opts: &WatchOpts,
+    opts: &Opts,
+    mut renode_config: RenodeRunConfig,
+    output_dir: &Path,
+) -> i32 {
+    let config_file = opts
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("Cargo.toml"));
+
+    loop {
+        apply_overrides(opts, &mut renode_config);
+        resolve_ports(&mut renode_config);
+        let port = renode_config
+            .cli
+            .port
+            .and_then(|p| p.fixed())
+            .ok_or(WatchError::MissingMonitorPort)
+            .unwrap();
+        let watch_config = renode_config.app.watch.clone().unwrap_or_default();
+        let build_command = envsub::envsub(&watch_config.build_command).unwrap();
+        if build_command.trim().is_empty() {
+            exit_with_error(WatchError::EmptyBuildCommand);
+        }
+        let poll_interval = Duration::from_millis(watch_config.poll_interval_ms);
+
+        let resc_def =
+            RescDefinition::new(&renode_config.resc, &renode_config.app, &watch_opts.input)
+                .unwrap();
+        let script = generate(opts, &resc_def, &renode_config, output_dir);
+
+        let mut sources = MtimeWatcher::new(
+            watch_config
+                .paths
+                .iter()
+                .map(|p| PathBuf::from(envsub::envsub(p).unwrap()))
+                .collect(),
+        );
+        let mut restart_files = MtimeWatcher::new(
+            std::iter::once(config_file.clone())
+                .chain(
+                    resc_def
+                        .platform_descriptions
+                        .iter()
+                        .filter_map(|p| p.source().map(Path::to_path_buf)),
+                )
+                // The local files generated descriptions import with `using`
+                .chain(
+                    resc_def
+                        .platform_descriptions
+                        .iter()
+                        .flat_map(|p| p.imports().iter().map(|i| i.source.clone())),
+                )
+                .chain(resc_def.assets.iter().map(|a| a.source.clone()))
+                .collect(),
+        );
+        let mut elf = MtimeWatcher::new(vec![watch_opts.input.clone()]);
+
+        let mut child = spawn_renode(
+            renode_command(opts, &renode_config, &resc_def, &script, output_dir)
+                .stdin(Stdio::inherit())
+                .stdout(Stdio::inherit()),
+        );
+        let mut running = true;
+        let mut mon: Option<MonitorClient> = None;
+
+        eprintln!("renode-run: watching for changes");
+        loop {
+            thread::sleep(poll_interval);
+
+            if running && child.try_wait().unwrap().is_some() {
+                eprintln!("renode-run: renode exited, waiting for changes to restart it");
+                running = false;
+                mon = None;
+            }
+
+            if sources.changed() {
+                eprintln!("renode-run: rebuilding with '{build_command}'");
+                let mut build_args = build_command.split_whitespace();
+                // SAFETY: the build command is checked to be non-empty
+                let status = process::Command::new(build_args.next().unwrap())
+                    .args(build_args)
+                    .status()
+                    .expect("Failed to start the build command");
+                if !status.success() {
+                    eprintln!("renode-run: build failed, waiting for changes");
+                    continue;
+                }
+            }
+
+            if restart_files.changed() {
+                eprintln!("renode-run: configuration changed, restarting renode");
+                break;
+            }
+            if !elf.changed() {
+                continue;
+            }
+            // The semihosting hooks are placed at addresses in the ELF
+            if !running || resc_def.semihosting.is_some() {
+                eprintln!("renode-run: ELF changed, restarting renode");
+                break;
+            }
+
+            eprintln!("renode-run: ELF changed, reloading");
+            let reloaded = reload(&mut mon, port, &resc_def);
+            if let Err(e) = reloaded {
+                eprintln!("renode-run: reload failed, restarting renode. {e}");
+                break;
+            }
+        }
+
+        timeout::wait(
+            &mut child,
+            None,
+            || true,
+            || {
+                mon.or_else(|| MonitorClient::connect(port, MONITOR_QUIT_TIMEOUT).ok())
+                    .map(|m| m.quit(MONITOR_QUIT_TIMEOUT).is_ok())
+                    .unwrap_or(false)
+            },
+        )
+        .unwrap();
+        renode_config = load_config(opts);
+    }
+}
+
+/// Re-runs the reset macro, which loads the ELF, in the running emulation
+fn reload(
+    mon: &mut Option<MonitorClient>,
+    port: u16,
+    resc_def: &RescDefinition,
+) -> Result<(), MonitorError> {
+    if mon.is_none() {
+        let mut m = MonitorClient::connect(port, monitor::CONNECT_TIMEOUT)?;
+        m.select_machine(&resc_def.machine_name)?;
+        *mon = Some(m);
+    }
+    // SAFETY: connected above
+    let m = mon.as_mut().unwrap();
+    m.pause()?;
+    m.run_macro("reset")?;
+    // The GDB server starts the emulation when the debugger attaches
+    if !resc_def.gdb.as_ref().is_some_and(|g| g.autostart_emulation) {
+        match resc_def.start.as_ref() {
+            Some(start) => m.execute(start).map(|_| ())?,
+            None => m.start()?,
+        }
+    }
+    Ok(())
+}
+
+fn run(opts: &Opts, mut renode_config: RenodeRunConfig, output_dir: &Path) -> i32 {
+    apply_overrides(opts, &mut renode_config);
+    resolve_ports(&mut renode_config);
+    if opts.interactive {
+        return interactive(opts, renode_config, output_dir);
+    }
+    let resc_def =
+        RescDefinition::new(&renode_config.resc, &renode_config.app, input(opts)).unwrap();
+    let script = generate(opts, &resc_def, &renode_config, output_dir);
+
+    if opts.no_run {
+        return 0;
+    }
+
+    let outcome = launch(
+        opts,
+        &renode_config,
+        &resc_def,
+        &script,
+        output_dir,
+        None,
+        || false,
+    );
+    outcome.report();
+    match outcome {
+        RunOutcome::Exited | RunOutcome::Stopped => 0,
+        RunOutcome::WallTimeout(_) | RunOutcome::VirtualTimeout(_) => timeout::EXIT_CODE,
+        RunOutcome::SemihostingExit(code) => code,
+    }
+}
+
+/// Runs renode in the background and hosts its monitor prompt,
+/// with the configured UARTs printed above the prompt
+fn interactive(opts: &Opts, renode_config: RenodeRunConfig, output_dir: &Path) -> i32 {
+    let port = renode_config
+        .cli
+        .port
+        .and_then(|p| p.fixed())
+        .ok_or(ConsoleError::MissingMonitorPort)
+        .unwrap();
+    let interactive_config = renode_config.app.interactive.clone().unwrap_or_default();
+    let mut resc_def =
+        RescDefinition::new(&renode_config.resc, &renode_config.app, input(opts)).unwrap();
+    for uart in interactive_config.uarts.iter() {
+        resc_def
+            .uart_captures
+            .push(envsub::envsub(uart.trim()).unwrap());
+    }
+    let script = generate(opts, &resc_def, &renode_config, output_dir);
+
+    if opts.no_run {
+        return 0;
+    }
+
+    let history_file = interactive_config
+        .history_file
+        .as_ref()
+        .map(|p| PathBuf::from(envsub::envsub(p).unwrap()));
+    let mut console = Console::new(history_file).unwrap();
+
+    let _ = fs::remove_file(output_dir.join(semihosting::OUTPUT_FILE_NAME));
+    for uart in resc_def.uart_captures.iter() {
+        let _ = fs::remove_file(tail::uart_output_path(output_dir, uart));
+    }
+
+    // Keep renode's own output away from the prompt
+    let log_path = output_dir.join(console::RENODE_LOG_FILE_NAME);
+    let log_file = File::create(&log_path).unwrap();
+    eprintln!(
+        "renode-run: renode output is written to '{}'",
+        log_path.display()
+    );
+
+    let mut child = spawn_renode(
+        renode_command(opts, &renode_config, &resc_def, &script, output_dir)
+            .stdin(Stdio::null())
+            .stdout(log_file.try_clone().unwrap())
+            .stderr(log_file),
+    );
+
+    let mut tails = Vec::new();
+    for uart in resc_def.uart_captures.iter() {
+        tails.push(FileTail::spawn(
+            tail::uart_output_path(output_dir, uart),
+            console.printer(),
+        ));
+    }
+    if resc_def.semihosting.is_some() {
+        tails.push(FileTail::spawn(
+            output_dir.join(semihosting::OUTPUT_FILE_NAME),
+            console.printer(),
+        ));
+    }
+
+    let mut mon = MonitorClient::connect(port, monitor::CONNECT_TIMEOUT).unwrap();
+    mon.select_machine(&resc_def.machine_name).unwrap();
+    let result = console.run(&mut mon);
+
+    timeout::wait(
+        &mut child,
+        None,
+        || true,
+        || mon.quit(MONITOR_QUIT_TIMEOUT).is_ok(),
+    )
+    .unwrap();
+    for t in tails.into_iter() {
+        t.stop();
+    }
+    result.unwrap();
+    0
+}
+
+/// Applies the CLI options that override configuration items
+fn apply_overrides(opts: &Opts, renode_config: &mut RenodeRunConfig) {
+    if let Some(t) = opts.virtual_timeout {
+        renode_config.resc.virtual_timeout = Some(format!("{}s", t.as_secs_f64()));
+    }
+    if let Some(t) = opts.timeout {
+        renode_config.app.timeout = Some(format!("{}s", t.as_secs_f64()));
+    }
+}
+
+/// Allocates the "auto" ports and exposes every port to substitutions and renode
+fn resolve_ports(renode_config: &mut RenodeRunConfig) {
+    for p in ports::resolve(renode_config).unwrap().iter() {
+        env::set_var(&p.env_var, p.port.to_string());
+        if p.auto {
+            eprintln!(
+                "renode-run: {} port {} (${{{}}})",
+                p.name, p.port, p.env_var
+            );
+        }
+    }
+}
+
+/// One of the application's outputs
+enum OutputSource {
+    Uart(String),
+    Rtt(u32),
+    Semihosting,
+}
+
+/// Sends an output somewhere other than stdout
+struct OutputTap {
+    source: OutputSource,
+    sink: Box<dyn Write + Send>,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum RunOutcome {
+    Exited,
+    /// Renode was terminated because renode-run was done with it
+    Stopped,
+    WallTimeout(Duration),
+    VirtualTimeout(Duration),
+    SemihostingExit(i32),
+}
+
+impl RunOutcome {
+    fn report(&self) {
+        match self {
+            RunOutcome::WallTimeout(t) => {
+                eprintln!("renode-run: wall-clock timeout of {t:?} expired, terminated renode")
+            }
+            RunOutcome::VirtualTimeout(t) => {
+                eprintln!("renode-run: virtual timeout of {t:?} expired, renode quit")
+            }
+            RunOutcome::SemihostingExit(code) => {
+                log::debug!("Application exited with semihosting exit code {code}")
+            }
+            RunOutcome::Exited | RunOutcome::Stopped => (),
+        }
+    }
+}
+
+/// Runs renode on the generated script, streaming the application's outputs
+/// until renode exits, a timeout fires, or `stop` returns true
+fn launch(
+    opts: &Opts,
+    renode_config: &RenodeRunConfig,
+    resc_def: &RescDefinition,
+    script: &Path,
+    output_dir: &Path,
+    tap: Option<OutputTap>,
+    stop: impl FnMut() -> bool,
+) -> RunOutcome {
+    let wall_timeout = renode_config
+        .app
+        .timeout
+        .as_ref()
+        .map(|t| timeout::parse_duration(&envsub::envsub(t).unwrap()).unwrap());
+
+    let mut rtt_reader = renode_config.app.rtt.as_ref().map(|cfg| {
+        let port = renode_config
+            .cli
+            .port
+            .and_then(|p| p.fixed())
+            .ok_or(RttError::MissingMonitorPort)
+            .unwrap();
+        let address = if let Some(addr) = cfg.control_block_address {
+            addr
+        } else {
+            ElfFile::read(input(opts))
+                .unwrap()
+                .symbol_address(&cfg.control_block_symbol)
+                .unwrap()
+                .ok_or_else(|| {
+                    RttError::ControlBlockSymbolNotFound(cfg.control_block_symbol.clone())
+                })
+                .unwrap()
+        };
+        RttReader::new(cfg, port, &resc_def.machine_name, address).unwrap()
+    });
+
+    let mut semihosting_sink: Option<Box<dyn Write + Send>> = None;
+    let mut uart_sinks: Vec<(String, Box<dyn Write + Send>)> = Vec::new();
+    if let Some(tap) = tap {
+        match tap.source {
+            OutputSource::Uart(uart) => uart_sinks.push((uart, tap.sink)),
+            OutputSource::Rtt(channel) => {
+                let found = rtt_reader
+                    .as_mut()
+                    .map(|r| r.set_output(channel, tap.sink))
+                    .unwrap_or(false);
+                if !found {
+                    exit_with_error(HarnessError::MissingRttChannel(channel));
+                }
+            }
+            OutputSource::Semihosting => semihosting_sink = Some(tap.sink),
+        }
+    }
+    for uart in resc_def.uart_captures.iter() {
+        if !uart_sinks.iter().any(|(u, _)| u == uart) {
+            uart_sinks.push((uart.clone(), Box::new(io::stdout())));
+        }
+    }
+
+    // Don't pick up the outputs of a previous run
+    let _ = fs::remove_file(output_dir.join(semihosting::EXIT_STATUS_FILE_NAME));
+    let _ = fs::remove_file(output_dir.join(semihosting::OUTPUT_FILE_NAME));
+    let _ = fs::remove_file(output_dir.join(timeout::VIRTUAL_TIMEOUT_MARKER_FILE_NAME));
+    for (uart, _) in uart_sinks.iter() {
+        let _ = fs::remove_file(tail::uart_output_path(output_dir, uart));
+    }
+
+    let mut child = spawn_renode(
+        renode_command(opts, renode_config, resc_def, script, output_dir)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit()),
+    );
+    let rtt_thread = rtt_reader.map(|r| r.spawn().unwrap());
+    let semihosting_tail = resc_def.semihosting.as_ref().map(|_| {
+        FileTail::spawn(
+            output_dir.join(semihosting::OUTPUT_FILE_NAME),
+            semihosting_sink.unwrap_or_else(|| Box::new(io::stdout())),
+        )
+    });
+    let uart_tails: Vec<FileTail> = uart_sinks
+        .into_iter()
+        .map(|(uart, sink)| FileTail::spawn(tail::uart_output_path(output_dir, &uart), sink))
+        .collect();
+
+    let monitor_port = renode_config.cli.port.and_then(|p| p.fixed());
+    let wait_outcome = timeout::wait(&mut child, wall_timeout, stop, || {
+        monitor_port
+            .map(|port| {
+                MonitorClient::connect(port, MONITOR_QUIT_TIMEOUT)
+                    .and_then(|m| m.quit(MONITOR_QUIT_TIMEOUT))
+                    .map_err(|e| log::debug!("Failed to quit renode through the monitor. {e}"))
+                    .is_ok()
+            })
+            .unwrap_or(false)
+    })
+    .unwrap();
+    if let Some(t) = rtt_thread {
+        let _ = t.join();
+    }
+    if let Some(t) = semihosting_tail {
+        t.stop();
+    }
+    for t in uart_tails.into_iter() {
+        t.stop();
+    }
+
+    match wait_outcome {
+        // SAFETY: the wait only times out when there's a timeout
+        WaitOutcome::TimedOut => return RunOutcome::WallTimeout(wall_timeout.unwrap()),
+        WaitOutcome::Stopped => return RunOutcome::Stopped,
+        WaitOutcome::Exited(_) => (),
+    }
+
+    if output_dir
+        .join(timeout::VIRTUAL_TIMEOUT_MARKER_FILE_NAME)
+        .exists()
+    {
+        // SAFETY: the marker is only generated when there's a virtual timeout
+        return RunOutcome::VirtualTimeout(resc_def.virtual_timeout.unwrap());
+    }
+
+    if resc_def.semihosting.is_some() {
+        if let Some(code) = semihosting::exit_code(output_dir) {
+            return RunOutcome::SemihostingExit(code);
+        }
+    }
+
+    RunOutcome::Exited
+}
+
+/// Generates the renode script and its imported files, returning the script's path
+fn generate(
+    opts: &Opts,
+    resc_def: &RescDefinition,
+    renode_config: &RenodeRunConfig,
+    output_dir: &Path,
+) -> PathBuf {
+    // Renode's own platform descriptions can only be checked against a local installation
+    let renode_root = if renode_config.app.backend == Backend::Native {
+        find_renode_bin(opts, renode_config)
+            .ok()
+            .and_then(|bin| renode::root(&bin))
+    } else {
+        None
+    };
+    if let Some(root) = renode_root.as_ref() {
+        for p in resc_def.platform_descriptions.iter() {
+            p.check_internal(root)
+                .unwrap_or_else(|e| exit_with_error(e));
+        }
+    }
+    let findings = repl::check(&resc_def.platform_descriptions, renode_root.as_deref());
+    for w in findings.warnings.iter() {
+        eprintln!("renode-run: warning: {w}");
+    }
+    for e in findings.errors.iter() {
+        eprintln!("renode-run: {e}");
+    }
+    if !findings.errors.is_empty() {
+        process::exit(1);
+    }
+    match memory::check_elf(
+        &resc_def.platform_descriptions,
+        renode_root.as_deref(),
+        input(opts),
+    ) {
+        Ok(Some(checked)) => {
+            for e in checked.unmapped.iter() {
+                if renode_config.app.allow_unmapped_memory {
+                    eprintln!("renode-run: warning: {e}");
+                } else {
+                    exit_with_error(e);
+                }
+            }
+            eprintln!("renode-run: memory usage\n{}", checked.usage);
+        }
+        Ok(None) => (),
+        Err(e) => exit_with_error(e),
+    }
 
     let output_file_path = renode_config
         .app
@@ -73,11 +1405,21 @@ fn main() {
         .as_ref()
         .map(PathBuf::from)
         .unwrap_or_else(|| output_dir.join("emulate.resc"));
+    // Generated platform descriptions are already named around the assets
+    if let Some(a) = resc_def
+        .assets
+        .iter()
+        .find(|a| a.output_path(output_dir) == output_file_path)
+    {
+        exit_with_error(AssetError::Script(
+            a.source.display().to_string(),
+            output_file_path.display().to_string(),
+        ));
+    }
 
     for p in resc_def.platform_descriptions.iter() {
-        if let PlatformDescriptionKind::GeneratedLocalFile(file_name) = p.kind() {
-            let out_path = output_dir.join(file_name);
-            fs::write(out_path, p.content()).unwrap();
+        for (file_name, content) in p.generated_files() {
+            fs::write(output_dir.join(file_name), content).unwrap();
         }
     }
 
@@ -86,37 +1428,180 @@ fn main() {
     let mut output_file = std::fs::File::create(&output_file_path).unwrap();
     let resc_gen = RescGen::new(&mut output_file);
     resc_gen
-        .generate(&output_dir, &renode_config.app, &resc_def)
+        .generate(output_dir, &renode_config.app, resc_def)
         .unwrap();
     output_file.sync_all().unwrap();
     drop(output_file);
 
-    if !opts.no_run {
-        let default_bin = PathBuf::from("renode");
-        let cfg_bin = renode_config
-            .app
-            .renode
-            .as_ref()
-            .map(|s| envsub::envsub(s).unwrap());
+    output_file_path
+}
 
-        let renode_bin = if let Some(opts_bin) = opts.renode_bin.as_ref() {
-            opts_bin.clone()
-        } else if let Some(cfgb) = cfg_bin {
-            PathBuf::from(cfgb)
-        } else {
-            default_bin
-        };
+/// The command that runs renode on the generated script, natively or in a container
+fn renode_command(
+    opts: &Opts,
+    renode_config: &RenodeRunConfig,
+    resc_def: &RescDefinition,
+    script: &Path,
+    output_dir: &Path,
+) -> process::Command {
+    let mut args = renode_config.cli.to_args();
+    let engine = match renode_config.app.backend {
+        Backend::Native => {
+            let renode_bin = renode_bin(opts, renode_config);
+            check_renode(&renode_bin, renode_config, resc_def);
+            log::debug!("Using renode bin '{}'", renode_bin.display());
+            args.insert(0, script.display().to_string());
+            let mut cmd = process::Command::new(renode_bin);
+            cmd.args(args)
+                .envs(renode_config.app.environment_variables.iter().cloned());
+            return cmd;
+        }
+        Backend::Docker => "docker",
+        Backend::Podman => "podman",
+    };
 
-        log::debug!("Using renode bin '{}'", renode_bin.display());
-        let mut args = renode_config.cli.to_args();
-        args.insert(0, output_file_path.display().to_string());
-        let mut child = Command::new(renode_bin)
-            .args(args)
-            .envs(renode_config.app.environment_variables)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .spawn()
-            .expect("Failed to start renode process");
-        let _exit_status = child.wait().unwrap();
+    let image = renode_config
+        .app
+        .image
+        .as_ref()
+        .map(|i| envsub::envsub(i).unwrap())
+        .ok_or_else(|| ContainerError::MissingImage(engine.to_owned()))
+        .unwrap_or_else(|e| exit_with_error(e));
+
+    // Everything the script refers to has to be mounted
+    let parent_dir = |p: &Path| {
+        p.parent()
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf()
+    };
+    let mut dirs = vec![
+        output_dir.to_path_buf(),
+        parent_dir(script),
+        parent_dir(input(opts)),
+    ];
+    for p in resc_def.platform_descriptions.iter() {
+        if let Some(src) = p.source() {
+            dirs.push(parent_dir(src));
+        }
+    }
+    let ports = [
+        renode_config.cli.port,
+        renode_config.resc.gdb.as_ref().map(|g| g.port),
+    ]
+    .into_iter()
+    .flatten()
+    .chain(renode_config.resc.socket_terminals.iter().map(|t| t.port))
+    .filter_map(|p| p.fixed())
+    .collect();
+
+    let container = Container::new(
+        engine,
+        &image,
+        env::current_dir().unwrap(),
+        &dirs,
+        ports,
+        renode_config.app.environment_variables.clone(),
+    )
+    .unwrap_or_else(|e| exit_with_error(e));
+    // Substituted files can refer to host paths too, e.g. an asset's ${RENODE_RUN_ASSET_*}
+    let substituted = resc_def
+        .platform_descriptions
+        .iter()
+        .flat_map(|p| p.generated_files())
+        .map(|(file_name, _)| output_dir.join(file_name))
+        .chain(
+            resc_def
+                .assets
+                .iter()
+                .filter(|a| a.substitute)
+                .map(|a| a.output_path(output_dir)),
+        );
+    for path in std::iter::once(script.to_path_buf()).chain(substituted) {
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, container.rewrite_paths(&content)).unwrap();
+    }
+    let script = fs::canonicalize(script).unwrap();
+    args.insert(0, container.rewrite_paths(&script.display().to_string()));
+
+    log::debug!("Using {engine} image '{image}'");
+    container.command(&args)
+}
+
+fn renode_bin(opts: &Opts, renode_config: &RenodeRunConfig) -> PathBuf {
+    find_renode_bin(opts, renode_config).unwrap_or_else(|e| exit_with_error(e))
+}
+
+/// Resolves the renode binary: CLI > config > toolchain selected by renode-version > discovery
+fn find_renode_bin(opts: &Opts, renode_config: &RenodeRunConfig) -> Result<PathBuf, RenodeError> {
+    let cfg_bin = renode_config
+        .app
+        .renode
+        .as_ref()
+        .map(|s| envsub::envsub(s).map(PathBuf::from))
+        .transpose()?;
+    if let Some(bin) = opts.renode_bin.clone().or(cfg_bin) {
+        return renode::discover(Some(bin));
+    }
+
+    let requirement = renode_config
+        .app
+        .renode_version
+        .as_ref()
+        .map(|r| renode::parse_requirement(&envsub::envsub(r)?))
+        .transpose()?;
+    if let Some(t) = Toolchains::open()
+        .ok()
+        .and_then(|t| t.select(requirement.as_ref()))
+    {
+        log::debug!("Using renode toolchain {}", t.version);
+        return Ok(t.bin());
+    }
+
+    renode::discover(None)
+}
+
+/// Checks that renode satisfies the configured renode-version and supports the script's features
+fn check_renode(renode_bin: &Path, renode_config: &RenodeRunConfig, resc_def: &RescDefinition) {
+    let requirement = renode_config
+        .app
+        .renode_version
+        .as_ref()
+        .map(|r| envsub::envsub(r).unwrap());
+    if let Err(e) = renode::check(
+        renode_bin,
+        requirement.as_deref(),
+        &Feature::used_by(resc_def),
+    ) {
+        exit_with_error(e);
+    }
+}
+
+/// Starts renode, natively or in a container
+fn spawn_renode(cmd: &mut process::Command) -> process::Child {
+    cmd.spawn().unwrap_or_else(|e| {
+        exit_with_error(RenodeError::Spawn(
+            cmd.get_program().to_string_lossy().into_owned(),
+            e.to_string(),
+        ))
+    })
+}
+
+fn exit_with_error(e: impl fmt::Display) -> ! {
+    eprintln!("renode-run: {e}");
+    process::exit(1);
+}
+
+/// The input ELF executable
+fn input(opts: &Opts) -> &Path {
+    // SAFETY: only the toolchain commands don't have an input, and they don't run renode
+    opts.input().unwrap()
+}
+
+fn gdb_bin(opts_bin: Option<&PathBuf>, gdb_config: &GdbConfig) -> PathBuf {
+    if let Some(opts_bin) = opts_bin {
+        opts_bin.clone()
+    } else {
+        PathBuf::from(envsub::envsub(&gdb_config.gdb).unwrap())
     }
 }
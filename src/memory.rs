@@ -0,0 +1,250 @@
+use crate::elf::{ElfError, ElfFile, Segment};
+use crate::repl::{self, fmt_range, MemoryRegion};
+use crate::types::PlatformDescription;
+use std::{fmt, path::Path};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum MemoryError {
+    #[error(
+        "The ELF segment {segment} isn't within the platform's memory ({regions}), check the memory layout the application is linked for (e.g. memory.x)"
+    )]
+    Unmapped { segment: String, regions: String },
+    #[error(
+        "The ELF segment {segment} overflows '{name}' {region} by {excess} bytes, check the memory layout the application is linked for (e.g. memory.x)"
+    )]
+    Overflow {
+        segment: String,
+        name: String,
+        region: String,
+        excess: u64,
+    },
+    #[error(transparent)]
+    Elf(#[from] ElfError),
+}
+
+/// How much of a memory region the application occupies
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Usage {
+    pub region: MemoryRegion,
+    pub used: u64,
+}
+
+/// The memory usage, printed as a table
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct UsageTable(pub Vec<Usage>);
+
+/// The ELF's memory usage, and the segments outside of the platform's memory.
+/// Those are errors unless the script's commands add the memory, see `allow-unmapped-memory`.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MemoryCheck {
+    pub usage: UsageTable,
+    pub unmapped: Vec<MemoryError>,
+}
+
+impl fmt::Display for UsageTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .0
+            .iter()
+            .map(|u| u.region.name.len())
+            .chain(std::iter::once("memory".len()))
+            .max()
+            .unwrap_or_default();
+        write!(
+            f,
+            "{:width$}  {:<10}  {:>10}  {:>10}",
+            "memory", "start", "size", "used"
+        )?;
+        for u in self.0.iter() {
+            let size = u.region.end - u.region.start;
+            write!(
+                f,
+                "\n{:width$}  {:#010x}  {:>10}  {:>10}  {:>5.1}%",
+                u.region.name,
+                u.region.start,
+                fmt_size(size),
+                fmt_size(u.used),
+                100.0 * u.used as f64 / size.max(1) as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the ELF's load segments are within the memory of the platform descriptions.
+/// Returns None when the memory map isn't completely known, e.g. renode's own platforms without its installation directory.
+pub fn check_elf(
+    descs: &[PlatformDescription],
+    renode_root: Option<&Path>,
+    elf: &Path,
+) -> Result<Option<MemoryCheck>, MemoryError> {
+    let loaded = repl::load(descs, renode_root);
+    let regions = repl::memory_regions(&loaded.descriptions);
+    if !loaded.complete || regions.is_empty() {
+        log::debug!("Skipping the memory check, the platform's memory map isn't known");
+        return Ok(None);
+    }
+    let segments = ElfFile::read(elf)?.load_segments()?;
+    check(&regions, &segments).map(Some)
+}
+
+/// Checks the segments that start in a region end within it, or the regions contiguous with it,
+/// returning the usage of every region and the segments that don't start in any
+pub fn check(regions: &[MemoryRegion], segments: &[Segment]) -> Result<MemoryCheck, MemoryError> {
+    let mut usage: Vec<Usage> = regions
+        .iter()
+        .map(|r| Usage {
+            region: r.clone(),
+            used: 0,
+        })
+        .collect();
+    let mut unmapped = Vec::new();
+    for s in segments.iter() {
+        let end = s.start.saturating_add(s.size);
+        let Some(region) = regions.iter().find(|r| (r.start..r.end).contains(&s.start)) else {
+            unmapped.push(MemoryError::Unmapped {
+                segment: fmt_segment(s),
+                regions: regions
+                    .iter()
+                    .map(|r| format!("'{}' {}", r.name, fmt_range(r.start, r.end)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            });
+            continue;
+        };
+        // e.g. the banks of a split SRAM
+        let mut contiguous_end = region.end;
+        while let Some(next) = regions
+            .iter()
+            .find(|r| r.start <= contiguous_end && r.end > contiguous_end)
+        {
+            contiguous_end = next.end;
+        }
+        if end > contiguous_end {
+            return Err(MemoryError::Overflow {
+                segment: fmt_segment(s),
+                name: region.name.clone(),
+                region: fmt_range(region.start, contiguous_end),
+                excess: end - contiguous_end,
+            });
+        }
+        for u in usage.iter_mut() {
+            let overlap = end
+                .min(u.region.end)
+                .saturating_sub(s.start.max(u.region.start));
+            u.used += overlap;
+        }
+    }
+    Ok(MemoryCheck {
+        usage: UsageTable(usage),
+        unmapped,
+    })
+}
+
+fn fmt_segment(s: &Segment) -> String {
+    let kind = if s.load { "loaded at" } else { "at" };
+    format!(
+        "{kind} {}",
+        fmt_range(s.start, s.start.saturating_add(s.size))
+    )
+}
+
+fn fmt_size(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_within_regions() {
+        let desc = repl::parse(
+            "stm32f4.repl",
+            "flash: Memory.MappedMemory @ { sysbus 0x08000000; sysbus 0x0 }\n    size: 0x100000\nsram: Memory.MappedMemory @ sysbus 0x20000000\n    size: 0x20000\nusart2: UART.STM32_UART @ sysbus <0x40004400, +0x100>\n",
+        )
+        .unwrap();
+        let regions = repl::memory_regions(&[desc]);
+        assert_eq!(
+            regions.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            vec!["flash", "flash", "sram"]
+        );
+
+        let text = Segment {
+            start: 0x0800_0000,
+            size: 0x2000,
+            load: false,
+        };
+        let data = Segment {
+            start: 0x0800_2000,
+            size: 0x400,
+            load: true,
+        };
+        let bss = Segment {
+            start: 0x2000_0000,
+            size: 0x1000,
+            load: false,
+        };
+        let checked = check(&regions, &[text, data, bss]).unwrap();
+        assert!(checked.unmapped.is_empty());
+        assert_eq!(checked.usage.0[1].used, 0x2400);
+        assert_eq!(checked.usage.0[2].used, 0x1000);
+        assert_eq!(
+            checked.usage.to_string(),
+            "memory  start             size        used
+flash   0x00000000     1.0 MiB         0 B    0.0%
+flash   0x08000000     1.0 MiB     9.0 KiB    0.9%
+sram    0x20000000   128.0 KiB     4.0 KiB    3.1%"
+        );
+
+        let ram_too_small = Segment {
+            start: 0x2001_f000,
+            size: 0x2000,
+            load: false,
+        };
+        assert_eq!(
+            check(&regions, &[ram_too_small]).unwrap_err().to_string(),
+            "The ELF segment at <0x2001f000, +0x2000> overflows 'sram' <0x20000000, +0x20000> by 4096 bytes, check the memory layout the application is linked for (e.g. memory.x)"
+        );
+        let other_mcu = Segment {
+            start: 0x1000_0000,
+            size: 0x100,
+            load: true,
+        };
+        assert_eq!(
+            check(&regions, &[other_mcu]).unwrap().unmapped,
+            vec![MemoryError::Unmapped {
+                segment: "loaded at <0x10000000, +0x100>".to_owned(),
+                regions: "'flash' <0x0, +0x100000>, 'flash' <0x8000000, +0x100000>, 'sram' <0x20000000, +0x20000>".to_owned(),
+            }]
+        );
+
+        // Segments can span contiguous regions
+        let banks = repl::parse(
+            "stm32f4.repl",
+            "sram1: Memory.MappedMemory @ sysbus 0x20000000\n    size: 0x1C000\nsram2: Memory.MappedMemory @ sysbus 0x2001C000\n    size: 0x4000\n",
+        )
+        .unwrap();
+        let regions = repl::memory_regions(&[banks]);
+        let across_banks = Segment {
+            start: 0x2001_b000,
+            size: 0x2000,
+            load: false,
+        };
+        let checked = check(&regions, &[across_banks]).unwrap();
+        assert_eq!(checked.usage.0[0].used, 0x1000);
+        assert_eq!(checked.usage.0[1].used, 0x1000);
+        assert_eq!(
+            check(&regions, &[ram_too_small]).unwrap_err().to_string(),
+            "The ELF segment at <0x2001f000, +0x2000> overflows 'sram2' <0x2001c000, +0x4000> by 4096 bytes, check the memory layout the application is linked for (e.g. memory.x)"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for renode to open the monitor port by default
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECT_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum MonitorError {
+    #[error("Failed to connect to the renode monitor on port {_0}. {_1}")]
+    Connect(u16, String),
+    #[error("The renode monitor connection was closed")]
+    Closed,
+    #[error("The renode monitor didn't respond in time")]
+    Timeout,
+    #[error("Monitor I/O error. {_0}")]
+    Io(String),
+    #[error("The monitor command '{command}' failed. {message}")]
+    Command { command: String, message: String },
+    #[error("Unexpected monitor response '{_0}'")]
+    UnexpectedResponse(String),
+}
+
+impl From<io::Error> for MonitorError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => MonitorError::Closed,
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => MonitorError::Timeout,
+            _ => MonitorError::Io(e.to_string()),
+        }
+    }
+}
+
+/// The output of a monitor command
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Response {
+    /// Everything the command printed, without the echoed command
+    pub output: String,
+    /// The lines renode printed as errors
+    pub errors: Vec<String>,
+    /// The context of the prompt that followed the output, i.e. the selected machine or `monitor`
+    pub prompt: String,
+}
+
+/// Blocking client for the renode monitor, i.e. the telnet port enabled by the `port` config
+pub struct MonitorClient {
+    stream: TcpStream,
+    prompt: String,
+}
+
+impl MonitorClient {
+    /// Connects to the monitor, retrying until renode opens the port or the timeout expires.
+    /// The banner has to be received within the timeout too.
+    pub fn connect(port: u16, timeout: Duration) -> Result<Self, MonitorError> {
+        let start = Instant::now();
+        let stream = loop {
+            match TcpStream::connect(("127.0.0.1", port)) {
+                Ok(s) => break s,
+                Err(e) if start.elapsed() > timeout => {
+                    return Err(MonitorError::Connect(port, e.to_string()))
+                }
+                Err(_) => thread::sleep(CONNECT_RETRY_INTERVAL),
+            }
+        };
+        let mut client = MonitorClient {
+            stream,
+            prompt: String::new(),
+        };
+        // Consume the banner and first prompt
+        client.set_timeout(Some(timeout))?;
+        client.read_response("")?;
+        client.set_timeout(None)?;
+        Ok(client)
+    }
+
+    /// The context of the most recent prompt
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    /// Runs a command, returning its output, or an error if renode reported one
+    pub fn execute(&mut self, command: &str) -> Result<String, MonitorError> {
+        let resp = self.execute_raw(command)?;
+        if resp.errors.is_empty() {
+            Ok(resp.output)
+        } else {
+            Err(MonitorError::Command {
+                command: command.to_owned(),
+                message: resp.errors.join(" "),
+            })
+        }
+    }
+
+    /// Runs a command, returning its response without checking it for errors
+    pub fn execute_raw(&mut self, command: &str) -> Result<Response, MonitorError> {
+        self.stream.write_all(command.as_bytes())?;
+        self.stream.write_all(b"\n")?;
+        self.read_response(command)
+    }
+
+    /// Selects the machine subsequent commands apply to
+    pub fn select_machine(&mut self, machine_name: &str) -> Result<(), MonitorError> {
+        self.execute(&format!("mach set \"{machine_name}\""))?;
+        Ok(())
+    }
+
+    pub fn start(&mut self) -> Result<(), MonitorError> {
+        self.execute("start")?;
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), MonitorError> {
+        self.execute("pause")?;
+        Ok(())
+    }
+
+    /// Runs a macro of the selected machine, e.g. `reset`
+    pub fn run_macro(&mut self, name: &str) -> Result<(), MonitorError> {
+        self.execute(&format!("runMacro ${name}"))?;
+        Ok(())
+    }
+
+    pub fn read_bytes(&mut self, address: u64, count: usize) -> Result<Vec<u8>, MonitorError> {
+        let resp = self.execute(&format!("sysbus ReadBytes 0x{address:X} {count}"))?;
+        let bytes: Vec<u8> = parse_hex_values(&resp)
+            .into_iter()
+            .map(|v| v as u8)
+            .collect();
+        if bytes.len() != count {
+            return Err(MonitorError::UnexpectedResponse(resp));
+        }
+        Ok(bytes)
+    }
+
+    pub fn write_double_word(&mut self, address: u64, value: u32) -> Result<(), MonitorError> {
+        self.execute(&format!("sysbus WriteDoubleWord 0x{address:X} 0x{value:X}"))?;
+        Ok(())
+    }
+
+    /// Asks renode to quit, which closes the connection.
+    /// Fails if renode doesn't accept the command and hang up within the timeout.
+    pub fn quit(mut self, timeout: Duration) -> Result<(), MonitorError> {
+        self.set_timeout(Some(timeout))?;
+        self.stream.write_all(b"quit\n")?;
+        let _ = self.stream.shutdown(Shutdown::Write);
+        // Renode doesn't answer, wait for it to hang up
+        let mut buf = [0_u8; 256];
+        let deadline = Instant::now() + timeout;
+        while self.stream.read(&mut buf)? != 0 {
+            if Instant::now() >= deadline {
+                return Err(MonitorError::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), MonitorError> {
+        // Zero durations are rejected, and would mean no timeout
+        let timeout = timeout.map(|t| t.max(Duration::from_millis(1)));
+        self.stream.set_read_timeout(timeout)?;
+        self.stream.set_write_timeout(timeout)?;
+        Ok(())
+    }
+
+    /// Read until the monitor prompt, returning everything before it
+    fn read_response(&mut self, command: &str) -> Result<Response, MonitorError> {
+        let mut raw = Vec::new();
+        let mut buf = [0_u8; 4096];
+        loop {
+            let n = self.stream.read(&mut buf)?;
+            if n == 0 {
+                return Err(MonitorError::Closed);
+            }
+            raw.extend_from_slice(&buf[..n]);
+            if let Some(resp) = parse_response(&strip_telnet(&raw), command) {
+                self.prompt = resp.prompt.clone();
+                return Ok(resp);
+            }
+        }
+    }
+}
+
+/// Parses a complete response, or returns None if the prompt hasn't been received yet.
+/// Renode prints errors in red, so those lines are picked out before the ANSI codes are stripped.
+fn parse_response(text: &str, command: &str) -> Option<Response> {
+    lazy_static! {
+        static ref PROMPT_RE: Regex =
+            Regex::new(r"\(([^()\n]*)\) $").expect("Could not construct prompt Regex");
+    }
+    let plain = strip_ansi(text);
+    let caps = PROMPT_RE.captures(&plain)?;
+    let prompt = caps[1].to_owned();
+
+    // The prompt is the last line
+    let body_lines = text.lines().count().saturating_sub(1);
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    let mut echoed = command.is_empty();
+    for line in text.lines().take(body_lines) {
+        let is_error = line.contains("\x1b[31");
+        let line = strip_ansi(line);
+        let trimmed = line.trim();
+        if trimmed.is_empty() && output.is_empty() {
+            continue;
+        }
+        // Drop the echoed command and the prompt it follows
+        if !echoed && trimmed.ends_with(command) {
+            echoed = true;
+            continue;
+        }
+        if is_error && !trimmed.is_empty() {
+            errors.push(trimmed.to_owned());
+        }
+        output.push(line);
+    }
+    while output.last().is_some_and(|l| l.trim().is_empty()) {
+        output.pop();
+    }
+
+    Some(Response {
+        output: output.join("\n"),
+        errors,
+        prompt,
+    })
+}
+
+/// Strips telnet negotiation sequences and carriage returns
+fn strip_telnet(raw: &[u8]) -> String {
+    const IAC: u8 = 0xFF;
+    const SB: u8 = 0xFA;
+    const SE: u8 = 0xF0;
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] != IAC {
+            bytes.push(raw[i]);
+            i += 1;
+        } else {
+            match raw.get(i + 1) {
+                Some(&SB) => {
+                    // Skip to the end of the sub-negotiation
+                    while i < raw.len() && !(raw[i] == IAC && raw.get(i + 1) == Some(&SE)) {
+                        i += 1;
+                    }
+                    i += 2;
+                }
+                Some(0xFB..=0xFE) => i += 3,
+                _ => i += 2,
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).replace('\r', "")
+}
+
+fn strip_ansi(s: &str) -> String {
+    lazy_static! {
+        static ref ANSI_RE: Regex =
+            Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").expect("Could not construct ANSI Regex");
+    }
+    ANSI_RE.replace_all(s, "").into_owned()
+}
+
+pub(crate) fn parse_hex_values(s: &str) -> Vec<u64> {
+    lazy_static! {
+        static ref HEX_RE: Regex =
+            Regex::new(r"0x([0-9A-Fa-f]+)").expect("Could not construct hex Regex");
+    }
+    HEX_RE
+        .captures_iter(s)
+        .filter_map(|c| u64::from_str_radix(&c[1], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses() {
+        let raw =
+            b"\xff\xfb\x01\xff\xfb\x03\x1b[32;1m[\r\n0x53, 0x45, 0x47\r\n]\x1b[0m\r\n(machine-0) ";
+        let text = strip_telnet(raw);
+        let resp = parse_response(&text, "sysbus ReadBytes 0x20000000 3").unwrap();
+        assert_eq!(resp.output, "[\n0x53, 0x45, 0x47\n]");
+        assert!(resp.errors.is_empty());
+        assert_eq!(resp.prompt, "machine-0");
+        assert_eq!(parse_hex_values(&resp.output), vec![0x53, 0x45, 0x47]);
+
+        let text =
+            "(monitor) foo\n\x1b[31;1mCould not find device or command: foo\x1b[0m\n(monitor) ";
+        let resp = parse_response(text, "foo").unwrap();
+        assert_eq!(resp.errors, vec!["Could not find device or command: foo"]);
+        assert_eq!(resp.prompt, "monitor");
+
+        assert_eq!(parse_response("[\n0x53", "x"), None);
+    }
+}
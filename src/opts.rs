@@ -1,32 +1,253 @@
-use clap::Parser;
-use std::path::PathBuf;
+use crate::{ide, timeout};
+use clap::{Parser, Subcommand};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 // TODO - surface more/all the available config items in opts
 
 /// Run embedded programs in the renode emulator
 #[derive(Parser, Debug, Clone, Default)]
-#[clap(version)]
+#[clap(version, subcommand_negates_reqs = true)]
 pub struct Opts {
     /// Path to renode binary.
     ///
     /// Useful if not on the user's $PATH.
-    #[clap(long = "renode", env = "RENODE_RUN_RENODE_BIN")]
+    #[clap(long = "renode", env = "RENODE_RUN_RENODE_BIN", global = true)]
     pub renode_bin: Option<PathBuf>,
 
     /// Path to toml configuration file.
     ///
     /// Defaults to resolving the current cargo workspace's Cargo.toml.
-    #[clap(short = 'c', long = "config", env = "RENODE_RUN_CONFIG_FILE")]
+    #[clap(
+        short = 'c',
+        long = "config",
+        env = "RENODE_RUN_CONFIG_FILE",
+        global = true
+    )]
     pub config: Option<PathBuf>,
 
     /// Generate output artifacts in this directory instead of a temporary directory
-    #[clap(short = 'o', long = "output", env = "RENODE_RUN_OUTPUT_DIR")]
+    #[clap(
+        short = 'o',
+        long = "output",
+        env = "RENODE_RUN_OUTPUT_DIR",
+        global = true
+    )]
     pub output_dir: Option<PathBuf>,
 
     /// Generate, but don't run the Renode script
     #[clap(long)]
     pub no_run: bool,
 
+    /// Host the renode monitor prompt, printing the configured UARTs above it.
+    ///
+    /// Requires the monitor port to be configured.
+    #[clap(short = 'i', long)]
+    pub interactive: bool,
+
+    /// Terminate renode after this much wall-clock time (e.g. '30s'), overrides the configured timeout
+    #[clap(
+        long,
+        env = "RENODE_RUN_TIMEOUT",
+        value_parser = timeout::parse_duration,
+        global = true
+    )]
+    pub timeout: Option<Duration>,
+
+    /// Terminate renode after this much emulated time (e.g. '5s'), overrides the configured virtual-timeout
+    #[clap(
+        long,
+        env = "RENODE_RUN_VIRTUAL_TIMEOUT",
+        value_parser = timeout::parse_duration,
+        global = true
+    )]
+    pub virtual_timeout: Option<Duration>,
+
+    /// Input ELF executable
+    #[clap(required = true)]
+    pub input: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Opts {
+    /// The input ELF executable, from either the subcommand or the top-level arguments.
+    /// Only the toolchain, init and platforms commands don't have one, it's optional for doctor.
+    pub fn input(&self) -> Option<&Path> {
+        match self.command.as_ref() {
+            Some(Command::Gdb(o)) => Some(&o.input),
+            Some(Command::Ide(o)) => Some(&o.input),
+            Some(Command::Test(o)) => Some(&o.input),
+            Some(Command::Robot(o)) => Some(&o.input),
+            Some(Command::Watch(o)) => Some(&o.input),
+            Some(Command::Doctor(o)) => o.input.as_deref(),
+            Some(Command::Toolchain(_)) | Some(Command::Init(_)) | Some(Command::Platforms(_)) => {
+                None
+            }
+            // clap requires the input when there's no subcommand
+            None => self.input.as_deref(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Launch gdb connected to the renode GDB server
+    Gdb(GdbOpts),
+    /// Generate editor debug configurations for the emulated firmware
+    Ide(IdeOpts),
+    /// Run a test suite, reporting the result of each test
+    Test(TestOpts),
+    /// Generate a Robot Framework suite from the configured expectations and run it with renode-test
+    Robot(RobotOpts),
+    /// Rebuild on source changes and reload the new ELF into the running emulation
+    Watch(WatchOpts),
+    /// Manage the portable renode builds selected by renode-version
+    Toolchain(ToolchainOpts),
+    /// Diagnose the renode installation, configuration and environment
+    Doctor(DoctorOpts),
+    /// Set up a project, adding the Cargo runner and a starter configuration
+    Init(InitOpts),
+    /// List the platform descriptions bundled with renode
+    Platforms(PlatformsOpts),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct GdbOpts {
+    /// Path to gdb binary, overrides the configured gdb
+    #[clap(long = "gdb", env = "RENODE_RUN_GDB_BIN")]
+    pub gdb_bin: Option<PathBuf>,
+
+    /// Port of the renode GDB server, overrides the configured gdb.port
+    #[clap(long)]
+    pub port: Option<u16>,
+
+    /// Input ELF executable
+    pub input: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct IdeOpts {
+    /// The kind of configuration to generate
+    #[clap(value_enum)]
+    pub format: ide::Format,
+
+    /// The debugger used by the generated configuration
+    #[clap(long, value_enum, default_value = "cortex-debug")]
+    pub debugger: ide::Debugger,
+
+    /// Directory to write the VS Code configuration files to
+    #[clap(long, default_value = ".vscode")]
+    pub vscode_dir: PathBuf,
+
+    /// Overwrite existing configuration files
+    #[clap(long)]
+    pub force: bool,
+
+    /// Input ELF executable
+    pub input: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct TestOpts {
+    /// Write a JUnit XML report to this file, overrides the configured test.junit
+    #[clap(long)]
+    pub junit: Option<PathBuf>,
+
+    /// Write a JSON summary to this file, overrides the configured test.json
+    #[clap(long)]
+    pub json: Option<PathBuf>,
+
+    /// Input ELF executable
+    pub input: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct RobotOpts {
+    /// Path to renode-test binary, overrides the configured renode-test
+    #[clap(long = "renode-test", env = "RENODE_RUN_RENODE_TEST_BIN")]
+    pub renode_test_bin: Option<PathBuf>,
+
+    /// Generate, but don't run the robot suite
+    #[clap(long)]
+    pub no_run: bool,
+
     /// Input ELF executable
     pub input: PathBuf,
 }
+
+#[derive(Parser, Debug, Clone)]
+pub struct WatchOpts {
+    /// Input ELF executable, as produced by the build command
+    pub input: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ToolchainOpts {
+    #[clap(subcommand)]
+    pub command: ToolchainCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ToolchainCommand {
+    /// Unpack a portable renode archive (e.g. renode-1.15.3.linux-portable.tar.gz) into the toolchains directory
+    Add(ToolchainAddOpts),
+    /// List the installed toolchains
+    List,
+    /// Use this toolchain when renode-version isn't configured
+    Default(ToolchainDefaultOpts),
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ToolchainAddOpts {
+    /// Replace the toolchain if its version is already installed
+    #[clap(long)]
+    pub force: bool,
+
+    /// Portable renode .tar.gz archive
+    pub archive: PathBuf,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct ToolchainDefaultOpts {
+    /// Version of an installed toolchain (e.g. 1.15.3)
+    pub version: String,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct DoctorOpts {
+    /// Input ELF executable, also checks the script generated for it
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct InitOpts {
+    /// Board from renode's platforms/boards, or a .repl path. Asks when not given.
+    #[clap(long)]
+    pub board: Option<String>,
+
+    /// Add a socket terminal for this UART (e.g. sysbus.usart2)
+    #[clap(long)]
+    pub uart: Option<String>,
+
+    /// Add a GDB server config
+    #[clap(long)]
+    pub gdb: bool,
+
+    /// Replace an existing runner in .cargo/config.toml
+    #[clap(long)]
+    pub force: bool,
+
+    /// List the available boards
+    #[clap(long)]
+    pub list: bool,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub struct PlatformsOpts {
+    /// Only list platforms whose path, CPU or imported files contain this (e.g. stm32f4 or cortex-m33)
+    pub query: Option<String>,
+}
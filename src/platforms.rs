@@ -0,0 +1,233 @@
+use crate::repl;
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const PLATFORMS_DIR_NAME: &str = "platforms";
+const REPL_FILE_EXT: &str = "repl";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum PlatformsError {
+    #[error("The platforms of renode '{_0}' could not be found, set RENODE_ROOT to its installation directory")]
+    NoRoot(String),
+    #[error("Failed to read the platforms directory '{_0}'. {_1}")]
+    Io(String, String),
+}
+
+/// A platform description file bundled with renode
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Platform {
+    /// The path used in configs, e.g. `@platforms/boards/stm32f4_discovery-kit.repl`
+    pub resc_path: String,
+    /// The `cpuType` of the CPUs, e.g. `cortex-m4f`
+    pub cpu_types: Vec<String>,
+    /// The CPU peripheral types, e.g. `CPU.CortexM`
+    pub cpu_models: Vec<String>,
+    pub peripherals: Vec<String>,
+    /// The files imported with `using`, relative to the renode root
+    pub imports: Vec<String>,
+}
+
+impl Platform {
+    /// Case-insensitive match of the path, CPU and imported files, e.g. `stm32f4` or `cortex-m33`
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        std::iter::once(&self.resc_path)
+            .chain(self.cpu_types.iter())
+            .chain(self.cpu_models.iter())
+            .chain(self.imports.iter())
+            .any(|s| s.to_lowercase().contains(&query))
+    }
+}
+
+/// Finds every `.repl` file under the renode root's `platforms` directory
+pub fn list(renode_root: &Path) -> Result<Vec<Platform>, PlatformsError> {
+    Ok(files(renode_root)?
+        .into_iter()
+        .map(|resc_path| {
+            let file = renode_root.join(resc_path.trim_start_matches('@'));
+            let mut platform = Platform {
+                resc_path,
+                cpu_types: Vec::new(),
+                cpu_models: Vec::new(),
+                peripherals: Vec::new(),
+                imports: Vec::new(),
+            };
+            let mut visited = BTreeSet::new();
+            scan(renode_root, &file, &mut platform, &mut visited);
+            platform
+        })
+        .collect())
+}
+
+/// The config paths of the `.repl` files under the renode root's `platforms` directory
+pub fn files(renode_root: &Path) -> Result<Vec<String>, PlatformsError> {
+    let dir = renode_root.join(PLATFORMS_DIR_NAME);
+    if !dir.is_dir() {
+        return Err(PlatformsError::NoRoot(renode_root.display().to_string()));
+    }
+    let mut files = Vec::new();
+    find_repl_files(&dir, &mut files)
+        .map_err(|e| PlatformsError::Io(dir.display().to_string(), e.to_string()))?;
+    files.sort();
+    Ok(files
+        .iter()
+        .filter_map(|f| Some(format!("@{}", f.strip_prefix(renode_root).ok()?.display())))
+        .collect())
+}
+
+/// The most similar platform path, if any is close enough to be a typo.
+/// File names are weighted equally to the whole path, since the shared `@platforms/` prefix
+/// makes every path look similar.
+pub fn closest<'a>(resc_path: &str, candidates: &'a [String]) -> Option<&'a str> {
+    const MIN_SIMILARITY: f64 = 0.7;
+    let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_lowercase();
+    let resc_path = resc_path.to_lowercase();
+    let resc_file_name = file_name(&resc_path);
+    candidates
+        .iter()
+        .map(|c| {
+            let path_similarity =
+                strsim::normalized_damerau_levenshtein(&resc_path, &c.to_lowercase());
+            let file_name_similarity =
+                strsim::normalized_damerau_levenshtein(&resc_file_name, &file_name(c));
+            ((path_similarity + file_name_similarity) / 2.0, c)
+        })
+        .filter(|(similarity, _)| *similarity >= MIN_SIMILARITY)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, c)| c.as_str())
+}
+
+fn find_repl_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let p = entry.path();
+        if p.is_dir() {
+            find_repl_files(&p, files)?;
+        } else if p.extension().is_some_and(|ext| ext == REPL_FILE_EXT) {
+            files.push(p);
+        }
+    }
+    Ok(())
+}
+
+/// Collects the peripherals and CPUs of a file and the files it imports.
+/// Renode resolves `using` paths relative to its root.
+fn scan(renode_root: &Path, file: &Path, platform: &mut Platform, visited: &mut BTreeSet<PathBuf>) {
+    if !visited.insert(file.to_path_buf()) {
+        return;
+    }
+    let Ok(content) = fs::read_to_string(file) else {
+        return;
+    };
+    let desc = match repl::parse(&file.display().to_string(), &content) {
+        Ok(d) => d,
+        Err(e) => {
+            log::debug!("Skipping unparsable platform. {e}");
+            return;
+        }
+    };
+
+    for using in desc.usings.iter() {
+        if !platform.imports.contains(&using.path) {
+            platform.imports.push(using.path.clone());
+        }
+        scan(
+            renode_root,
+            &renode_root.join(&using.path),
+            platform,
+            visited,
+        );
+    }
+    for e in desc.entries.iter() {
+        if !platform.peripherals.contains(&e.name) {
+            platform.peripherals.push(e.name.clone());
+        }
+        let Some(ty) = e.type_name.as_ref().filter(|t| t.starts_with("CPU.")) else {
+            continue;
+        };
+        if !platform.cpu_models.contains(ty) {
+            platform.cpu_models.push(ty.clone());
+        }
+        if let Some(cpu_type) = e
+            .property("cpuType")
+            .map(|t| t.trim_matches('"').to_owned())
+        {
+            if !platform.cpu_types.contains(&cpu_type) {
+                platform.cpu_types.push(cpu_type);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanned_platforms() {
+        let root = tempfile::tempdir().unwrap();
+        let cpus = root.path().join("platforms").join("cpus");
+        let boards = root.path().join("platforms").join("boards");
+        fs::create_dir_all(&cpus).unwrap();
+        fs::create_dir_all(&boards).unwrap();
+        fs::write(
+            cpus.join("stm32f4.repl"),
+            r#"
+// An STM32F4 MCU
+cpu: CPU.CortexM @ sysbus
+    cpuType: "cortex-m4f"
+    nvic: nvic
+
+nvic: IRQControllers.NVIC @ sysbus 0xE000E000
+    -> cpu@0
+
+usart2: UART.STM32_UART @ sysbus <0x40004400, +0x100>
+    -> nvic@38
+"#,
+        )
+        .unwrap();
+        fs::write(
+            boards.join("stm32f4_discovery-kit.repl"),
+            r#"using "platforms/cpus/stm32f4.repl"
+
+UserButton: Miscellaneous.Button @ gpioPortA
+    -> gpioPortA@0
+"#,
+        )
+        .unwrap();
+
+        let platforms = list(root.path()).unwrap();
+        assert_eq!(platforms.len(), 2);
+        let board = &platforms[0];
+        assert_eq!(
+            board.resc_path,
+            "@platforms/boards/stm32f4_discovery-kit.repl"
+        );
+        assert_eq!(board.cpu_types, vec!["cortex-m4f".to_owned()]);
+        assert_eq!(board.cpu_models, vec!["CPU.CortexM".to_owned()]);
+        assert_eq!(
+            board.peripherals,
+            vec!["cpu", "nvic", "usart2", "UserButton"]
+        );
+        assert!(board.matches("STM32F4"));
+        assert!(board.matches("m4f"));
+        assert!(!board.matches("nrf52"));
+
+        let files = files(root.path()).unwrap();
+        assert_eq!(
+            closest("@platforms/boards/stm32f4_discovery.repl", &files),
+            Some("@platforms/boards/stm32f4_discovery-kit.repl")
+        );
+        assert_eq!(
+            closest("@platforms/cpus/STM32F4.repl", &files),
+            Some("@platforms/cpus/stm32f4.repl")
+        );
+        assert_eq!(
+            closest("@platforms/board/stm32f4_discovery-kit.repl", &files),
+            Some("@platforms/boards/stm32f4_discovery-kit.repl")
+        );
+        assert_eq!(closest("@platforms/cpus/nrf52840.repl", &files), None);
+    }
+}
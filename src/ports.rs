@@ -0,0 +1,143 @@
+use crate::config::{PortConfig, RenodeRunConfig};
+use crate::envsub::{envsub, EnvSubError};
+use std::net::TcpListener;
+
+pub const MONITOR_PORT_ENV_VAR: &str = "RENODE_RUN_MONITOR_PORT";
+pub const GDB_PORT_ENV_VAR: &str = "RENODE_RUN_GDB_PORT";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum PortError {
+    #[error("Failed to allocate a free port. {_0}")]
+    Allocate(String),
+    #[error("The field 'socket-terminals.uart' cannot contain an empty string")]
+    EmptyUart,
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// A port in use by the emulation
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ResolvedPort {
+    pub name: String,
+    /// Exposed to substitutions and renode's environment
+    pub env_var: String,
+    pub port: u16,
+    /// Whether renode-run picked the port
+    pub auto: bool,
+}
+
+/// The environment variable of a socket terminal's port, e.g. `RENODE_RUN_UART_USART3_PORT` for `sysbus.usart3`
+pub fn uart_port_env_var(uart: &str) -> String {
+    let local_name = uart.rsplit('.').next().unwrap_or(uart);
+    let name: String = local_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("RENODE_RUN_UART_{name}_PORT")
+}
+
+/// Replaces each `"auto"` port in the config with a free port, returning all the configured ports.
+///
+/// The listeners are held until every port is picked so they're distinct, there's
+/// still a window between releasing them and renode binding them.
+pub fn resolve(cfg: &mut RenodeRunConfig) -> Result<Vec<ResolvedPort>, PortError> {
+    let mut listeners = Vec::new();
+    let mut resolved = Vec::new();
+
+    if let Some(p) = cfg.cli.port.as_mut() {
+        resolved.push(resolve_port(
+            p,
+            "monitor".to_owned(),
+            MONITOR_PORT_ENV_VAR.to_owned(),
+            &mut listeners,
+        )?);
+    }
+    if let Some(gdb) = cfg.resc.gdb.as_mut() {
+        resolved.push(resolve_port(
+            &mut gdb.port,
+            "GDB server".to_owned(),
+            GDB_PORT_ENV_VAR.to_owned(),
+            &mut listeners,
+        )?);
+    }
+    for t in cfg.resc.socket_terminals.iter_mut() {
+        let uart = envsub(t.uart.trim())?;
+        if uart.is_empty() {
+            return Err(PortError::EmptyUart);
+        }
+        resolved.push(resolve_port(
+            &mut t.port,
+            format!("{uart} socket terminal"),
+            uart_port_env_var(&uart),
+            &mut listeners,
+        )?);
+    }
+
+    Ok(resolved)
+}
+
+fn resolve_port(
+    port: &mut PortConfig,
+    name: String,
+    env_var: String,
+    listeners: &mut Vec<TcpListener>,
+) -> Result<ResolvedPort, PortError> {
+    let (p, auto) = match *port {
+        PortConfig::Fixed(p) => (p, false),
+        PortConfig::Auto => {
+            let listener = TcpListener::bind(("127.0.0.1", 0))
+                .map_err(|e| PortError::Allocate(e.to_string()))?;
+            let p = listener
+                .local_addr()
+                .map_err(|e| PortError::Allocate(e.to_string()))?
+                .port();
+            listeners.push(listener);
+            (p, true)
+        }
+    };
+    *port = PortConfig::Fixed(p);
+    Ok(ResolvedPort {
+        name,
+        env_var,
+        port: p,
+        auto,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{GdbConfig, SocketTerminalConfig};
+
+    #[test]
+    fn auto_ports() {
+        let mut cfg = RenodeRunConfig::default();
+        cfg.cli.port = Some(PortConfig::Auto);
+        cfg.resc.gdb = Some(GdbConfig::default());
+        cfg.resc.socket_terminals.push(SocketTerminalConfig {
+            uart: "sysbus.usart3".to_owned(),
+            port: PortConfig::Auto,
+            name: None,
+        });
+
+        let ports = resolve(&mut cfg).unwrap();
+        assert_eq!(ports.len(), 3);
+        assert!(ports[0].auto);
+        assert_eq!(ports[0].env_var, MONITOR_PORT_ENV_VAR);
+        assert_eq!(ports[1].port, 3333);
+        assert!(!ports[1].auto);
+        assert_eq!(ports[2].env_var, "RENODE_RUN_UART_USART3_PORT");
+        assert_ne!(ports[0].port, ports[2].port);
+        assert_eq!(cfg.cli.port, Some(PortConfig::Fixed(ports[0].port)));
+        assert_eq!(
+            cfg.resc.socket_terminals[0].port,
+            PortConfig::Fixed(ports[2].port)
+        );
+    }
+}
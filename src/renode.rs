@@ -0,0 +1,342 @@
+use crate::envsub::EnvSubError;
+use crate::types::RescDefinition;
+use derive_more::Display;
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::{Version, VersionReq};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const RENODE_BIN_NAME: &str = "renode";
+pub const RENODE_ROOT_ENV_VAR: &str = "RENODE_ROOT";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum RenodeError {
+    #[error("The renode binary '{_0}' could not be found")]
+    BinNotFound(String),
+    #[error(
+        "renode could not be found, searched {}. Install renode or use --renode, the 'renode' config or RENODE_ROOT",
+        _0.join(", ")
+    )]
+    NotFound(Vec<String>),
+    #[error("Failed to start renode '{_0}'. {_1}")]
+    Spawn(String, String),
+    #[error("Failed to determine the version of renode '{_0}'. {_1}")]
+    Version(String, String),
+    #[error("Invalid renode-version requirement '{_0}'. {_1}")]
+    Requirement(String, String),
+    #[error("renode {version} doesn't satisfy the renode-version requirement '{requirement}'")]
+    Unsatisfied {
+        version: String,
+        requirement: String,
+    },
+    #[error("The generated script uses {feature}, which requires renode {minimum} or newer, found renode {version}")]
+    FeatureUnsupported {
+        feature: Feature,
+        minimum: String,
+        version: String,
+    },
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// Commands emitted by the generated script that older renode releases don't have
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+pub enum Feature {
+    #[display("the GDB server (StartGdbServer)")]
+    GdbServer,
+    #[display("a GDB server CPU cluster (gdb.cpu-cluster)")]
+    GdbCpuCluster,
+    #[display("the semihosting UART (UART.SemihostingUart)")]
+    SemihostingUart,
+    #[display("UART file backends (CreateFileBackend)")]
+    FileBackends,
+    #[display("socket terminals (CreateServerSocketTerminal)")]
+    SocketTerminals,
+    #[display("the logging configuration (logLevel, LogFunctionNames, LogPeripheralAccess)")]
+    Logging,
+    #[display("virtual-timeout (emulation RunFor)")]
+    RunFor,
+    #[display("Python hooks (AddHook, python)")]
+    PythonHooks,
+}
+
+impl Feature {
+    /// The oldest renode release that has the commands
+    pub fn minimum_version(&self) -> Version {
+        match self {
+            Feature::GdbServer => Version::new(1, 11, 0),
+            Feature::GdbCpuCluster => Version::new(1, 15, 0),
+            Feature::SemihostingUart => Version::new(1, 13, 0),
+            Feature::FileBackends => Version::new(1, 11, 0),
+            Feature::SocketTerminals => Version::new(1, 11, 0),
+            Feature::Logging => Version::new(1, 13, 0),
+            Feature::RunFor => Version::new(1, 13, 0),
+            Feature::PythonHooks => Version::new(1, 13, 0),
+        }
+    }
+
+    /// The features the generated script uses for a definition, mirroring `RescGen`
+    pub fn used_by(resc: &RescDefinition) -> Vec<Feature> {
+        let mut features = Vec::new();
+        if let Some(gdb) = resc.gdb.as_ref() {
+            features.push(Feature::GdbServer);
+            if gdb.cpu_cluster.is_some() {
+                features.push(Feature::GdbCpuCluster);
+            }
+        }
+        if resc.semihosting.is_some() {
+            // The output is written to a file backend and SYS_EXIT is handled by hooks
+            features.extend([
+                Feature::SemihostingUart,
+                Feature::FileBackends,
+                Feature::PythonHooks,
+            ]);
+        }
+        if !resc.uart_captures.is_empty() {
+            features.push(Feature::FileBackends);
+        }
+        if !resc.socket_terminals.is_empty() {
+            features.push(Feature::SocketTerminals);
+        }
+        if resc.logging.is_some() {
+            features.push(Feature::Logging);
+        }
+        if resc.virtual_timeout.is_some() {
+            // The timeout's marker file is created by a python command
+            features.extend([Feature::RunFor, Feature::PythonHooks]);
+        }
+        features.sort();
+        features.dedup();
+        features
+    }
+}
+
+/// Resolves the renode binary. An explicit binary (from the CLI or config) is used as is,
+/// otherwise `RENODE_ROOT`, `$PATH` and the common install locations are searched in order.
+pub fn discover(explicit: Option<PathBuf>) -> Result<PathBuf, RenodeError> {
+    if let Some(bin) = explicit {
+        return if bin.components().count() > 1 {
+            if bin.is_file() {
+                Ok(bin)
+            } else {
+                Err(RenodeError::BinNotFound(bin.display().to_string()))
+            }
+        } else {
+            find_in_path(&bin).ok_or_else(|| RenodeError::BinNotFound(bin.display().to_string()))
+        };
+    }
+
+    let mut searched = Vec::new();
+    if let Some(root) = env::var_os(RENODE_ROOT_ENV_VAR) {
+        let root = PathBuf::from(root);
+        if let Some(bin) = bin_in_root(&root) {
+            return Ok(bin);
+        }
+        searched.push(format!("{RENODE_ROOT_ENV_VAR} ({})", root.display()));
+    }
+
+    if let Some(bin) = find_in_path(Path::new(RENODE_BIN_NAME)) {
+        return Ok(bin);
+    }
+    searched.push("$PATH".to_owned());
+
+    for root in install_roots() {
+        if let Some(bin) = bin_in_root(&root) {
+            return Ok(bin);
+        }
+        searched.push(root.display().to_string());
+    }
+
+    Err(RenodeError::NotFound(searched))
+}
+
+/// Runs `renode --version`
+pub fn version(bin: &Path) -> Result<Version, RenodeError> {
+    let output = Command::new(bin)
+        .arg("--version")
+        .output()
+        .map_err(|e| RenodeError::Version(bin.display().to_string(), e.to_string()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_version(&stdout).ok_or_else(|| {
+        RenodeError::Version(
+            bin.display().to_string(),
+            format!("Unexpected output '{}'", stdout.trim()),
+        )
+    })
+}
+
+/// Checks renode's version against the configured requirement and the features in use.
+/// The version is only queried when there's something to check, since renode is slow to start.
+pub fn check(
+    bin: &Path,
+    requirement: Option<&str>,
+    features: &[Feature],
+) -> Result<Option<Version>, RenodeError> {
+    let requirement = requirement.map(parse_requirement).transpose()?;
+    if requirement.is_none() && features.is_empty() {
+        return Ok(None);
+    }
+
+    let version = version(bin)?;
+    log::debug!("Found renode {version} at '{}'", bin.display());
+    check_version(&version, requirement.as_ref(), features)?;
+    Ok(Some(version))
+}
+
+/// Checks a known renode version against a requirement and the features in use
+pub fn check_version(
+    version: &Version,
+    requirement: Option<&VersionReq>,
+    features: &[Feature],
+) -> Result<(), RenodeError> {
+    if let Some(req) = requirement {
+        if !req.matches(version) {
+            return Err(RenodeError::Unsatisfied {
+                version: version.to_string(),
+                requirement: req.to_string(),
+            });
+        }
+    }
+    if let Some(f) = features.iter().find(|f| *version < f.minimum_version()) {
+        return Err(RenodeError::FeatureUnsupported {
+            feature: *f,
+            minimum: f.minimum_version().to_string(),
+            version: version.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// The installation directory containing renode's `platforms`, which `@platforms/...` paths are relative to
+pub fn root(bin: &Path) -> Option<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Ok(bin) = bin.canonicalize() {
+        candidates.extend(bin.ancestors().skip(1).take(2).map(Path::to_path_buf));
+    }
+    if let Some(root) = env::var_os(RENODE_ROOT_ENV_VAR) {
+        candidates.push(PathBuf::from(root));
+    }
+    candidates.push(PathBuf::from("/opt/renode"));
+    candidates
+        .into_iter()
+        .find(|root| root.join("platforms").is_dir())
+}
+
+pub fn parse_requirement(req: &str) -> Result<VersionReq, RenodeError> {
+    VersionReq::parse(req.trim())
+        .map_err(|e| RenodeError::Requirement(req.to_owned(), e.to_string()))
+}
+
+/// Parses the output of `renode --version`, e.g. `Renode v1.14.0.8227 (fd7a4bb1-202306301024)`.
+/// The build number is dropped.
+pub fn parse_version(s: &str) -> Option<Version> {
+    lazy_static! {
+        static ref VERSION_RE: Regex =
+            Regex::new(r"(\d+)\.(\d+)\.(\d+)").expect("Could not construct version Regex");
+    }
+    let caps = VERSION_RE.captures(s)?;
+    Some(Version::new(
+        caps[1].parse().ok()?,
+        caps[2].parse().ok()?,
+        caps[3].parse().ok()?,
+    ))
+}
+
+fn bin_in_root(root: &Path) -> Option<PathBuf> {
+    [
+        root.join(RENODE_BIN_NAME),
+        root.join("bin").join(RENODE_BIN_NAME),
+    ]
+    .into_iter()
+    .find(|p| p.is_file())
+}
+
+pub fn find_in_path(bin: &Path) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(bin))
+        .find(|p| p.is_file())
+}
+
+/// `/opt/renode` and the directories portable releases are typically extracted to,
+/// e.g. `~/renode_1.14.0_portable`
+fn install_roots() -> Vec<PathBuf> {
+    let mut roots = vec![PathBuf::from("/opt/renode")];
+    let mut parents = vec![PathBuf::from("/opt")];
+    if let Some(home) = env::var_os("HOME") {
+        parents.push(PathBuf::from(home));
+    }
+    for parent in parents.iter() {
+        let Ok(entries) = fs::read_dir(parent) else {
+            continue;
+        };
+        let mut portable: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.is_dir()
+                    && p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("renode") && n.ends_with("portable"))
+            })
+            .collect();
+        // Prefer the newest release
+        portable.sort_by_key(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(parse_version)
+        });
+        roots.extend(portable.into_iter().rev());
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(
+            parse_version("Renode v1.14.0.8227 (fd7a4bb1-202306301024)"),
+            Some(Version::new(1, 14, 0))
+        );
+        assert_eq!(
+            parse_version("Renode, version 1.15.3.30173 (2ba4d3e7-202409121258)"),
+            Some(Version::new(1, 15, 3))
+        );
+        assert_eq!(
+            parse_version("renode_1.13.3_portable"),
+            Some(Version::new(1, 13, 3))
+        );
+        assert_eq!(parse_version("Renode"), None);
+
+        let req = parse_requirement(">=1.14").unwrap();
+        assert!(req.matches(&Version::new(1, 15, 0)));
+        assert!(!req.matches(&Version::new(1, 13, 3)));
+        assert!(parse_requirement("latest").is_err());
+    }
+
+    #[test]
+    fn feature_versions() {
+        let v1_14 = Version::new(1, 14, 0);
+        assert_eq!(
+            check_version(&v1_14, None, &[Feature::GdbServer, Feature::RunFor]),
+            Ok(())
+        );
+        assert_eq!(
+            check_version(&v1_14, None, &[Feature::GdbServer, Feature::GdbCpuCluster])
+                .unwrap_err()
+                .to_string(),
+            "The generated script uses a GDB server CPU cluster (gdb.cpu-cluster), which requires renode 1.15.0 or newer, found renode 1.14.0"
+        );
+        let req = parse_requirement(">=1.15").unwrap();
+        assert!(matches!(
+            check_version(&v1_14, Some(&req), &[]),
+            Err(RenodeError::Unsatisfied { .. })
+        ));
+    }
+}
@@ -0,0 +1,1084 @@
+use crate::types::{GeneratedImport, PlatformDescription, PlatformDescriptionKind};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum ReplError {
+    #[error("{at}: {message}")]
+    Syntax { at: String, message: String },
+    #[error("{at}: the peripheral '{name}' is already declared at {first_at}")]
+    DuplicatePeripheral {
+        name: String,
+        at: String,
+        first_at: String,
+    },
+    /// `other` describes the earlier declaration, e.g. `<0x20000000, +0x1000> of 'sram' at file:1`
+    #[error("{at}: the sysbus range {range} of '{name}' overlaps {other}")]
+    OverlappingRanges {
+        name: String,
+        at: String,
+        range: String,
+        other: String,
+    },
+    #[error("{at}: the imported file '{path}' could not be found")]
+    ImportNotFound { path: String, at: String },
+    #[error("Failed to read the platform description '{_0}'. {_1}")]
+    Io(String, String),
+}
+
+pub(crate) fn fmt_range(start: u64, end: u64) -> String {
+    format!("<{start:#x}, +{:#x}>", end - start)
+}
+
+/// A parsed `.repl` file or string
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Description {
+    /// Where the description comes from, used in errors
+    pub file: String,
+    /// The index of the platform description this was loaded for, itself or one it imports
+    pub load: usize,
+    /// One of renode's own files
+    pub trusted: bool,
+    pub usings: Vec<Using>,
+    pub entries: Vec<Entry>,
+}
+
+/// A `using "path" [prefixed "prefix"]` import
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Using {
+    pub path: String,
+    pub prefix: Option<String>,
+    pub line: usize,
+}
+
+/// A peripheral declaration `name: Type @ registration`, or an update of one when it has no type
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub type_name: Option<String>,
+    pub registrations: Option<Vec<Registration>>,
+    pub properties: Vec<Property>,
+    pub line: usize,
+}
+
+impl Entry {
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .rev()
+            .find(|p| p.key == key)
+            .map(|p| p.value.as_str())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Registration {
+    pub parent: String,
+    pub address: Address,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Address {
+    None,
+    Point(u64),
+    /// Start and exclusive end
+    Range(u64, u64),
+    /// Registrations like `sysbus new Bus.BusPointRegistration { ... }`
+    Other(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Property {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+/// A line with the lines it continues onto, i.e. open brackets or multi-line strings
+struct LogicalLine {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+fn syntax(file: &str, line: usize, message: impl Into<String>) -> ReplError {
+    ReplError::Syntax {
+        at: format!("{file}:{line}"),
+        message: message.into(),
+    }
+}
+
+/// Parses a platform description, `file` names it in errors
+pub fn parse(file: &str, content: &str) -> Result<Description, ReplError> {
+    let mut desc = Description {
+        file: file.to_owned(),
+        load: 0,
+        trusted: false,
+        usings: Vec::new(),
+        entries: Vec::new(),
+    };
+    let mut entry: Option<Entry> = None;
+    // The indentation of the `init:` line while in an init block
+    let mut init_indent: Option<usize> = None;
+
+    for line in logical_lines(file, content)? {
+        let text = line.text.trim();
+        if line.indent == 0 {
+            desc.entries.extend(entry.take());
+            init_indent = None;
+            if text.starts_with("using ") || text.starts_with("using\"") {
+                desc.usings.push(parse_using(file, line.number, text)?);
+            } else {
+                entry = Some(parse_entry(file, line.number, text)?);
+            }
+            continue;
+        }
+
+        let Some(e) = entry.as_mut() else {
+            return Err(syntax(
+                file,
+                line.number,
+                "indented attribute outside of a peripheral declaration",
+            ));
+        };
+        match init_indent {
+            // Init commands are monitor commands, they aren't checked
+            Some(indent) if line.indent > indent => continue,
+            _ => init_indent = None,
+        }
+        if parse_attribute(file, line.number, text, e)? {
+            init_indent = Some(line.indent);
+        }
+    }
+    desc.entries.extend(entry);
+    Ok(desc)
+}
+
+/// Splits the content into logical lines, dropping comments
+fn logical_lines(file: &str, content: &str) -> Result<Vec<LogicalLine>, ReplError> {
+    let mut lines = Vec::new();
+    let mut current: Option<LogicalLine> = None;
+    let mut depth: usize = 0;
+    let mut comment_start: Option<usize> = None;
+    let mut string_start: Option<(usize, &str)> = None;
+
+    for (i, raw) in content.lines().enumerate() {
+        let number = i + 1;
+        let mut text = String::new();
+        let mut rest = raw;
+        while let Some(c) = rest.chars().next() {
+            if comment_start.is_some() {
+                match rest.find("*/") {
+                    Some(end) => {
+                        comment_start = None;
+                        rest = &rest[end + 2..];
+                    }
+                    None => rest = "",
+                }
+                continue;
+            }
+            if let Some((_, delim)) = string_start {
+                match rest.find(delim) {
+                    Some(end) => {
+                        text.push_str(&rest[..end + delim.len()]);
+                        string_start = None;
+                        rest = &rest[end + delim.len()..];
+                    }
+                    None => {
+                        text.push_str(rest);
+                        rest = "";
+                    }
+                }
+                continue;
+            }
+
+            if rest.starts_with("//") {
+                break;
+            } else if rest.starts_with("/*") {
+                comment_start = Some(number);
+                rest = &rest[2..];
+            } else if let Some(delim) = ["'''", "\"\"\""].into_iter().find(|d| rest.starts_with(d))
+            {
+                string_start = Some((number, delim));
+                text.push_str(delim);
+                rest = &rest[3..];
+            } else if c == '"' {
+                let end = rest[1..]
+                    .find('"')
+                    .ok_or_else(|| syntax(file, number, "unterminated string"))?;
+                text.push_str(&rest[..end + 2]);
+                rest = &rest[end + 2..];
+            } else {
+                match c {
+                    '[' | '{' => depth += 1,
+                    ']' | '}' => {
+                        depth = depth
+                            .checked_sub(1)
+                            .ok_or_else(|| syntax(file, number, format!("unmatched '{c}'")))?;
+                    }
+                    _ => (),
+                }
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+
+        match current.as_mut() {
+            Some(l) => {
+                l.text.push(' ');
+                l.text.push_str(&text);
+            }
+            None if !text.trim().is_empty() => {
+                current = Some(LogicalLine {
+                    number,
+                    indent: raw.len() - raw.trim_start().len(),
+                    text,
+                })
+            }
+            None => (),
+        }
+        if depth == 0 && string_start.is_none() {
+            lines.extend(current.take());
+        }
+    }
+
+    if let Some(line) = comment_start {
+        return Err(syntax(file, line, "unterminated comment"));
+    }
+    if let Some((line, _)) = string_start {
+        return Err(syntax(file, line, "unterminated multi-line string"));
+    }
+    if let Some(l) = current {
+        return Err(syntax(file, l.number, "unclosed bracket"));
+    }
+    Ok(lines)
+}
+
+/// Removes the indentation a description has from being written inside a config string,
+/// keeping the indentation of attributes and init blocks relative to their declaration.
+///
+/// When the first line has none, e.g. it follows the opening quotes, the base indentation
+/// is taken from the declarations and imports on the following lines.
+pub fn normalize_indentation(content: &str) -> String {
+    let is_blank = |l: &&str| l.trim().is_empty();
+    let indent = |l: &str| l.len() - l.trim_start_matches([' ', '\t']).len();
+    let mut lines: Vec<&str> = content.lines().skip_while(is_blank).collect();
+    while lines.last().is_some_and(is_blank) {
+        lines.pop();
+    }
+
+    let base = match lines.first() {
+        Some(first) if indent(first) == 0 => lines
+            .iter()
+            .skip(1)
+            .filter(|l| is_statement(l.trim_start()))
+            .map(|l| indent(l))
+            .min()
+            .unwrap_or(0),
+        _ => lines
+            .iter()
+            .filter(|l| !is_blank(l))
+            .map(|l| indent(l))
+            .min()
+            .unwrap_or(0),
+    };
+    lines
+        .iter()
+        .map(|l| &l[indent(l).min(base)..])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces the paths of the `using` imports, `f` returns the new path or None to keep it
+pub fn rewrite_usings(content: &str, mut f: impl FnMut(&str) -> Option<String>) -> String {
+    lazy_static! {
+        static ref USING_PATH_RE: Regex = Regex::new(r#"(?m)^(\s*using\s*")([^"]+)(")"#)
+            .expect("Could not construct using path Regex");
+    }
+    USING_PATH_RE
+        .replace_all(content, |caps: &regex::Captures| {
+            let path = f(&caps[2]).unwrap_or_else(|| caps[2].to_owned());
+            format!("{}{path}{}", &caps[1], &caps[3])
+        })
+        .into_owned()
+}
+
+/// Whether a line starts a top-level statement, i.e. a `using` import or a typed peripheral declaration
+fn is_statement(text: &str) -> bool {
+    lazy_static! {
+        static ref STATEMENT_RE: Regex = Regex::new(
+            r#"^(using\s*"|[A-Za-z_][A-Za-z0-9_]*\s*:\s*(@|[A-Za-z_][A-Za-z0-9_]*\.[A-Za-z0-9_.]*|[A-Z][A-Za-z0-9_]*\s*@))"#
+        )
+        .expect("Could not construct statement Regex");
+    }
+    STATEMENT_RE.is_match(text)
+}
+
+fn parse_using(file: &str, line: usize, text: &str) -> Result<Using, ReplError> {
+    lazy_static! {
+        static ref USING_RE: Regex =
+            Regex::new(r#"^using\s*"([^"]+)"(?:\s+prefixed\s+"([^"]*)")?$"#)
+                .expect("Could not construct using Regex");
+    }
+    let caps = USING_RE.captures(text).ok_or_else(|| {
+        syntax(
+            file,
+            line,
+            "expected an import like 'using \"platforms/cpus/stm32f4.repl\"'",
+        )
+    })?;
+    Ok(Using {
+        path: caps[1].to_owned(),
+        prefix: caps.get(2).map(|p| p.as_str().to_owned()),
+        line,
+    })
+}
+
+fn parse_entry(file: &str, line: usize, text: &str) -> Result<Entry, ReplError> {
+    lazy_static! {
+        static ref ENTRY_RE: Regex = Regex::new(r"(?s)^([A-Za-z_][A-Za-z0-9_]*)\s*:(.*)$")
+            .expect("Could not construct entry Regex");
+        static ref TYPE_RE: Regex =
+            Regex::new(r"^[A-Za-z_][A-Za-z0-9_.]*$").expect("Could not construct type Regex");
+    }
+    let caps = ENTRY_RE.captures(text).ok_or_else(|| {
+        syntax(
+            file,
+            line,
+            "expected a peripheral declaration like 'uart0: UART.PL011 @ sysbus 0x1000' or a 'using' import",
+        )
+    })?;
+    let mut entry = Entry {
+        name: caps[1].to_owned(),
+        type_name: None,
+        registrations: None,
+        properties: Vec::new(),
+        line,
+    };
+    let mut rest = caps.get(2).map(|m| m.as_str()).unwrap_or_default().trim();
+
+    if !rest.is_empty() && !rest.starts_with('@') && !rest.starts_with('{') {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let type_name = &rest[..end];
+        if !TYPE_RE.is_match(type_name) {
+            return Err(syntax(
+                file,
+                line,
+                format!("invalid peripheral type '{type_name}'"),
+            ));
+        }
+        entry.type_name = Some(type_name.to_owned());
+        rest = rest[end..].trim_start();
+    }
+
+    if let Some(after_at) = rest.strip_prefix('@') {
+        let after_at = after_at.trim_start();
+        let (registrations, after) = if after_at.starts_with('{') {
+            let end = matching_brace(after_at)
+                .ok_or_else(|| syntax(file, line, "unclosed registration list"))?;
+            let regs = split_top_level(&after_at[1..end])
+                .into_iter()
+                .map(|r| parse_registration(file, line, r))
+                .collect::<Result<Vec<_>, _>>()?;
+            (regs, &after_at[end + 1..])
+        } else {
+            let end = registration_end(after_at);
+            let reg = parse_registration(file, line, &after_at[..end])?;
+            (vec![reg], &after_at[end..])
+        };
+        entry.registrations = Some(registrations);
+        rest = after.trim_start();
+        if let Some(after_as) = rest.strip_prefix("as ") {
+            let alias_end = after_as
+                .trim_start()
+                .strip_prefix('"')
+                .and_then(|a| a.find('"').map(|i| i + 2))
+                .ok_or_else(|| syntax(file, line, "expected a quoted alias after 'as'"))?;
+            rest = after_as.trim_start()[alias_end..].trim_start();
+        }
+    }
+
+    if rest.starts_with('{') {
+        let end = matching_brace(rest).ok_or_else(|| syntax(file, line, "unclosed attributes"))?;
+        for attr in split_top_level(&rest[1..end]) {
+            parse_attribute(file, line, attr, &mut entry)?;
+        }
+        rest = rest[end + 1..].trim_start();
+    }
+
+    if !rest.is_empty() {
+        return Err(syntax(file, line, format!("unexpected '{rest}'")));
+    }
+    Ok(entry)
+}
+
+/// Parses a property, interrupt connection or init block header, returning true for the latter
+fn parse_attribute(
+    file: &str,
+    line: usize,
+    text: &str,
+    entry: &mut Entry,
+) -> Result<bool, ReplError> {
+    lazy_static! {
+        static ref KEY_RE: Regex =
+            Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("Could not construct key Regex");
+    }
+    // Property values can contain '->', so only an arrow before any ':' is a connection
+    let arrow = text.find("->");
+    let is_connection = arrow.is_some_and(|a| text.find(':').is_none_or(|c| a < c));
+    if let Some((_, target)) = text.split_once("->").filter(|_| is_connection) {
+        let valid = target.split_once('@').is_some_and(|(peripheral, irq)| {
+            !peripheral.trim().is_empty() && !irq.trim().is_empty()
+        });
+        if !valid {
+            return Err(syntax(
+                file,
+                line,
+                "expected an interrupt connection like '-> nvic@12'",
+            ));
+        }
+        return Ok(false);
+    }
+
+    let Some((key, value)) = text.split_once(':') else {
+        return Err(syntax(
+            file,
+            line,
+            format!(
+                "expected a property, an interrupt connection or an init block, found '{text}'"
+            ),
+        ));
+    };
+    let key = key.trim();
+    let value = value.trim();
+    if key == "init" || key == "init add" || key == "reset" {
+        return Ok(value.is_empty());
+    }
+    if !KEY_RE.is_match(key) {
+        return Err(syntax(file, line, format!("invalid property name '{key}'")));
+    }
+    if value.is_empty() {
+        return Err(syntax(
+            file,
+            line,
+            format!("the property '{key}' doesn't have a value"),
+        ));
+    }
+    entry.properties.push(Property {
+        key: key.to_owned(),
+        value: value.to_owned(),
+        line,
+    });
+    Ok(false)
+}
+
+fn parse_registration(file: &str, line: usize, text: &str) -> Result<Registration, ReplError> {
+    let text = text.trim();
+    let (parent, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    if parent.is_empty() {
+        return Err(syntax(file, line, "missing registration after '@'"));
+    }
+    let rest = rest.trim();
+    let invalid = || syntax(file, line, format!("invalid address '{rest}'"));
+
+    let address = if rest.is_empty() {
+        Address::None
+    } else if let Some(range) = rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')) {
+        let (start, end) = range.split_once(',').ok_or_else(invalid)?;
+        let start = parse_number(start).ok_or_else(invalid)?;
+        let end = match end.trim().strip_prefix('+') {
+            Some(size) => start.saturating_add(parse_number(size).ok_or_else(invalid)?),
+            // The end is inclusive
+            None => parse_number(end).ok_or_else(invalid)?.saturating_add(1),
+        };
+        Address::Range(start, end)
+    } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        Address::Point(parse_number(rest).ok_or_else(invalid)?)
+    } else {
+        Address::Other(rest.to_owned())
+    };
+    Ok(Registration {
+        parent: parent.to_owned(),
+        address,
+    })
+}
+
+pub fn parse_number(s: &str) -> Option<u64> {
+    let s = s.trim().replace('_', "");
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// The index of the brace closing the one `s` starts with
+fn matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Where a single registration ends, i.e. at the attributes or alias.
+/// Registrations like `sysbus new Bus.BusPointRegistration { address: 0x0; cpu: cpu }` include their braces.
+fn registration_end(s: &str) -> usize {
+    let end = s
+        .find('{')
+        .into_iter()
+        .chain(s.find(" as ").map(|i| i + 1))
+        .min()
+        .unwrap_or(s.len());
+    if s[..end].split_whitespace().any(|t| t == "new") && s[end..].starts_with('{') {
+        matching_brace(&s[end..])
+            .map(|close| end + close + 1)
+            .unwrap_or(s.len())
+    } else {
+        end
+    }
+}
+
+/// Splits on the `;` that aren't nested in braces
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth -= 1,
+            ';' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter().filter(|p| !p.trim().is_empty()).collect()
+}
+
+/// The descriptions loaded for a machine, with the files they import before them
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Loaded {
+    pub descriptions: Vec<Description>,
+    pub errors: Vec<ReplError>,
+    /// Whether every description and import could be read and parsed,
+    /// renode's own can't be without its installation directory
+    pub complete: bool,
+}
+
+/// Parses the platform descriptions and the files they import
+pub fn load(descs: &[PlatformDescription], renode_root: Option<&Path>) -> Loaded {
+    let mut loader = Loader {
+        renode_root,
+        ancestors: Vec::new(),
+        loaded: Loaded {
+            complete: true,
+            ..Default::default()
+        },
+    };
+    for (i, desc) in descs.iter().enumerate() {
+        let (file, content, dir, trusted) = match desc.kind() {
+            PlatformDescriptionKind::Internal => {
+                let Some(root) = renode_root else {
+                    loader.loaded.complete = false;
+                    continue;
+                };
+                let path = root.join(desc.content().trim_start_matches('@'));
+                match fs::read_to_string(&path) {
+                    Ok(content) => (desc.content().to_owned(), content, root.to_path_buf(), true),
+                    Err(e) => {
+                        loader.error(ReplError::Io(desc.content().to_owned(), e.to_string()));
+                        continue;
+                    }
+                }
+            }
+            PlatformDescriptionKind::LocalFile => {
+                // SAFETY: local files have a source
+                let path = desc.source().unwrap();
+                match fs::read_to_string(path) {
+                    Ok(content) => (path.display().to_string(), content, parent_dir(path), false),
+                    Err(e) => {
+                        loader.error(ReplError::Io(path.display().to_string(), e.to_string()));
+                        continue;
+                    }
+                }
+            }
+            PlatformDescriptionKind::GeneratedLocalFile(_) => {
+                // SAFETY: local files have a source
+                let path = desc.source().unwrap();
+                (
+                    path.display().to_string(),
+                    desc.content().to_owned(),
+                    parent_dir(path),
+                    false,
+                )
+            }
+            PlatformDescriptionKind::String => (
+                format!("platform description {}", i + 1),
+                desc.content().to_owned(),
+                PathBuf::from("."),
+                false,
+            ),
+        };
+        // A generated description's imports may refer back to it
+        loader.ancestors = desc
+            .imports()
+            .iter()
+            .take(1)
+            .map(|i| i.source.clone())
+            .collect();
+        let first = loader.loaded.descriptions.len();
+        loader.load(&file, &content, &dir, trusted, desc.imports());
+        for d in loader.loaded.descriptions[first..].iter_mut() {
+            d.load = i;
+        }
+    }
+    loader.loaded
+}
+
+/// What `check` and `validate` found
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Findings {
+    /// Duplicate peripherals and overlapping sysbus ranges in the user's own descriptions
+    pub errors: Vec<ReplError>,
+    /// Everything else, e.g. syntax the parser doesn't know, renode has the final say on those
+    pub warnings: Vec<ReplError>,
+}
+
+/// Parses the platform descriptions and the files they import, then checks them for duplicate
+/// peripherals and overlapping sysbus ranges.
+///
+/// Renode's own files are only checked for conflicts, syntax the parser doesn't know
+/// shouldn't prevent using them. They're skipped entirely when its root isn't known.
+pub fn check(descs: &[PlatformDescription], renode_root: Option<&Path>) -> Findings {
+    let loaded = load(descs, renode_root);
+    let mut findings = validate(&loaded.descriptions);
+    findings.warnings.extend(loaded.errors);
+    findings
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|d| !d.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+struct Loader<'a> {
+    renode_root: Option<&'a Path>,
+    /// The files being loaded, to skip circular imports
+    ancestors: Vec<PathBuf>,
+    loaded: Loaded,
+}
+
+impl Loader<'_> {
+    fn error(&mut self, e: ReplError) {
+        self.loaded.errors.push(e);
+        self.loaded.complete = false;
+    }
+
+    /// Parses a description and, before it, the files it imports.
+    /// Renode resolves imports relative to its root, local files may also import relative to themselves.
+    /// The imports of generated descriptions refer to the files generated with them.
+    fn load(
+        &mut self,
+        file: &str,
+        content: &str,
+        dir: &Path,
+        trusted: bool,
+        generated: &[GeneratedImport],
+    ) {
+        let mut desc = match parse(file, content) {
+            Ok(d) => d,
+            Err(e) if trusted => {
+                log::debug!("Skipping renode's platform description. {e}");
+                self.loaded.complete = false;
+                return;
+            }
+            Err(e) => {
+                self.error(e);
+                return;
+            }
+        };
+
+        desc.trusted = trusted;
+        for using in desc.usings.iter() {
+            let first = self.loaded.descriptions.len();
+            if let Some(import) = generated.iter().find(|i| i.file_name == using.path) {
+                if self.ancestors.contains(&import.source) {
+                    continue;
+                }
+                self.ancestors.push(import.source.clone());
+                self.load(
+                    &import.source.display().to_string(),
+                    &import.content,
+                    &parent_dir(&import.source),
+                    false,
+                    generated,
+                );
+                self.ancestors.pop();
+            } else {
+                let candidates = self
+                    .renode_root
+                    .map(|root| root.join(&using.path))
+                    .into_iter()
+                    .chain(std::iter::once(dir.join(&using.path)));
+                let Some(path) = candidates
+                    .filter_map(|p| p.canonicalize().ok())
+                    .find(|p| p.is_file())
+                else {
+                    // Without renode's root its imports can't be resolved
+                    if self.renode_root.is_some() {
+                        self.error(ReplError::ImportNotFound {
+                            path: using.path.clone(),
+                            at: format!("{file}:{}", using.line),
+                        });
+                    } else {
+                        self.loaded.complete = false;
+                    }
+                    continue;
+                };
+                if self.ancestors.contains(&path) {
+                    continue;
+                }
+                let content = match fs::read_to_string(&path) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        self.error(ReplError::Io(path.display().to_string(), e.to_string()));
+                        continue;
+                    }
+                };
+                let imported_trusted = self
+                    .renode_root
+                    .and_then(|root| root.canonicalize().ok())
+                    .is_some_and(|root| path.starts_with(root));
+
+                self.ancestors.push(path.clone());
+                self.load(
+                    &path.display().to_string(),
+                    &content,
+                    &parent_dir(&path),
+                    imported_trusted,
+                    &[],
+                );
+                self.ancestors.pop();
+            }
+            if let Some(prefix) = using.prefix.as_ref() {
+                add_prefix(&mut self.loaded.descriptions[first..], prefix);
+            }
+        }
+        self.loaded.descriptions.push(desc);
+    }
+}
+
+/// Prefixes the names declared by imported descriptions, and the references to them
+fn add_prefix(descs: &mut [Description], prefix: &str) {
+    let declared: Vec<String> = descs
+        .iter()
+        .flat_map(|d| d.entries.iter().map(|e| e.name.clone()))
+        .collect();
+    for e in descs.iter_mut().flat_map(|d| d.entries.iter_mut()) {
+        e.name = format!("{prefix}{}", e.name);
+        for r in e.registrations.iter_mut().flatten() {
+            if declared.contains(&r.parent) {
+                r.parent = format!("{prefix}{}", r.parent);
+            }
+        }
+    }
+}
+
+/// Checks for peripherals declared twice and overlapping sysbus ranges,
+/// later entries for a peripheral update its registration and properties.
+/// Conflicts only between renode's own files are warnings.
+pub fn validate(descs: &[Description]) -> Findings {
+    let mut findings = Findings::default();
+    let mut declared: BTreeMap<&str, (&Description, usize)> = BTreeMap::new();
+    for d in descs.iter() {
+        for e in d.entries.iter().filter(|e| e.type_name.is_some()) {
+            match declared.get(e.name.as_str()) {
+                // Declaring an imported peripheral again replaces it
+                Some((first, _)) if first.load == d.load && first.file != d.file => {
+                    declared.insert(&e.name, (d, e.line));
+                }
+                Some((first, first_line)) => {
+                    let e = ReplError::DuplicatePeripheral {
+                        name: e.name.clone(),
+                        at: format!("{}:{}", d.file, e.line),
+                        first_at: format!("{}:{first_line}", first.file),
+                    };
+                    if first.trusted && d.trusted {
+                        findings.warnings.push(e);
+                    } else {
+                        findings.errors.push(e);
+                    }
+                }
+                None => {
+                    declared.insert(&e.name, (d, e.line));
+                }
+            }
+        }
+    }
+
+    let ranges = sysbus_ranges(descs);
+    for (i, a) in ranges.iter().enumerate() {
+        for b in ranges[i + 1..].iter() {
+            if b.start >= a.end {
+                break;
+            }
+            if a.name != b.name {
+                let e = ReplError::OverlappingRanges {
+                    name: b.name.to_owned(),
+                    at: format!("{}:{}", b.file, b.line),
+                    range: fmt_range(b.start, b.end),
+                    other: format!(
+                        "{} of '{}' at {}:{}",
+                        fmt_range(a.start, a.end),
+                        a.name,
+                        a.file,
+                        a.line
+                    ),
+                };
+                if a.trusted && b.trusted {
+                    findings.warnings.push(e);
+                } else {
+                    findings.errors.push(e);
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// A memory peripheral's range on the system bus
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub start: u64,
+    /// Exclusive
+    pub end: u64,
+}
+
+/// The sysbus ranges of the memory peripherals, i.e. `Memory.*` types like `Memory.MappedMemory`
+pub fn memory_regions(descs: &[Description]) -> Vec<MemoryRegion> {
+    let mut types: BTreeMap<&str, &str> = BTreeMap::new();
+    for e in descs.iter().flat_map(|d| d.entries.iter()) {
+        if let Some(t) = e.type_name.as_ref() {
+            types.insert(&e.name, t);
+        }
+    }
+    sysbus_ranges(descs)
+        .into_iter()
+        .filter(|r| types.get(r.name).is_some_and(|t| t.starts_with("Memory.")))
+        .map(|r| MemoryRegion {
+            name: r.name.to_owned(),
+            start: r.start,
+            end: r.end,
+        })
+        .collect()
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+struct SysbusRange<'a> {
+    start: u64,
+    end: u64,
+    name: &'a str,
+    file: &'a str,
+    line: usize,
+    trusted: bool,
+}
+
+/// The sysbus ranges of the peripherals, sorted by address.
+/// Later registrations and sizes of a peripheral replace earlier ones.
+fn sysbus_ranges(descs: &[Description]) -> Vec<SysbusRange<'_>> {
+    let mut registrations: BTreeMap<&str, (&[Registration], &Description, usize)> = BTreeMap::new();
+    let mut sizes: BTreeMap<&str, u64> = BTreeMap::new();
+    for d in descs.iter() {
+        for e in d.entries.iter() {
+            if let Some(regs) = e.registrations.as_ref() {
+                registrations.insert(&e.name, (regs, d, e.line));
+            }
+            if let Some(size) = e.property("size").and_then(parse_number) {
+                sizes.insert(&e.name, size);
+            }
+        }
+    }
+
+    let mut ranges = Vec::new();
+    for (name, (regs, desc, line)) in registrations.into_iter() {
+        for r in regs.iter().filter(|r| r.parent == "sysbus") {
+            let range = match r.address {
+                Address::Range(start, end) => Some((start, end)),
+                Address::Point(start) => sizes
+                    .get(name)
+                    .map(|size| (start, start.saturating_add(*size))),
+                Address::None | Address::Other(_) => None,
+            };
+            if let Some((start, end)) = range {
+                ranges.push(SysbusRange {
+                    start,
+                    end,
+                    name,
+                    file: &desc.file,
+                    line,
+                    trusted: desc.trusted,
+                });
+            }
+        }
+    }
+    ranges.sort();
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_and_validation() {
+        let cpu = parse(
+            "stm32f4.repl",
+            r#"
+// Flash and RAM
+flash: Memory.MappedMemory @ { sysbus 0x08000000; sysbus 0x0 }
+    size: 0x100000
+
+sram: Memory.MappedMemory @ sysbus 0x20000000
+    size: 0x20000
+
+/* The UART
+   used by the examples */
+usart2: UART.STM32_UART @ sysbus <0x40004400, +0x100> { frequency: 16000000; -> nvic@38 }
+
+nvic: IRQControllers.NVIC @ sysbus new Bus.BusPointRegistration { address: 0xE000E000; cpu: cpu } as "intc"
+    -> cpu@0
+
+cpu: CPU.CortexM @ sysbus
+    cpuType: "cortex-m4f"
+    nvic: nvic
+    init:
+        Tag <0x40000000, 0x40000003> "UNKNOWN"
+        Tag <0x40000004, 0x40000007> "OTHER"
+
+sysbus:
+    init add:
+        Tag <0x50000000, 0x50000003> "EXTRA"
+"#,
+        )
+        .unwrap();
+        assert_eq!(cpu.entries.len(), 6);
+        assert_eq!(
+            cpu.entries[0].registrations,
+            Some(vec![
+                Registration {
+                    parent: "sysbus".to_owned(),
+                    address: Address::Point(0x0800_0000),
+                },
+                Registration {
+                    parent: "sysbus".to_owned(),
+                    address: Address::Point(0),
+                },
+            ])
+        );
+        assert_eq!(
+            cpu.entries[2].registrations.as_ref().unwrap()[0].address,
+            Address::Range(0x4000_4400, 0x4000_4500)
+        );
+        assert_eq!(cpu.entries[2].property("frequency"), Some("16000000"));
+        assert_eq!(cpu.entries[3].line, 13);
+        assert_eq!(cpu.entries[4].property("cpuType"), Some("\"cortex-m4f\""));
+        assert_eq!(cpu.entries[5].type_name, None);
+
+        let board = parse(
+            "board.repl",
+            "using \"platforms/cpus/stm32f4.repl\"\n\nccm: Memory.MappedMemory @ sysbus 0x2001F000\n    size: 0x10000\n\nusart2: UART.STM32_UART @ sysbus <0x40004800, +0x100>\n",
+        )
+        .unwrap();
+        assert_eq!(board.usings[0].path, "platforms/cpus/stm32f4.repl");
+
+        // The board imports the CPU, declaring usart2 again replaces it
+        let errors = validate(&[cpu.clone(), board.clone()]).errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "board.repl:3: the sysbus range <0x2001f000, +0x10000> of 'ccm' overlaps <0x20000000, +0x20000> of 'sram' at stm32f4.repl:6"
+        );
+
+        // Loaded separately it can't
+        let separate = Description { load: 1, ..board };
+        let errors = validate(&[cpu.clone(), separate.clone()]).errors;
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "board.repl:6: the peripheral 'usart2' is already declared at stm32f4.repl:11"
+        );
+
+        // Conflicts between renode's own files are only warnings
+        let trusted = |d: Description| Description { trusted: true, ..d };
+        let findings = validate(&[trusted(cpu), trusted(separate)]);
+        assert!(findings.errors.is_empty());
+        assert_eq!(findings.warnings.len(), 2);
+
+        let arrow = parse(
+            "x",
+            "uart0: UART.PL011 @ sysbus 0x1000\n    name: \"a->b\"\n",
+        )
+        .unwrap();
+        assert_eq!(arrow.entries[0].property("name"), Some("\"a->b\""));
+
+        assert_eq!(
+            parse("x", "uart0: UART.PL011 @ sysbus 0x1000\n    -> gic\n").map(|_| ()),
+            Err(syntax(
+                "x",
+                2,
+                "expected an interrupt connection like '-> nvic@12'"
+            ))
+        );
+        assert_eq!(
+            parse("x", "\n    size: 0x1000\n").map(|_| ()),
+            Err(syntax(
+                "x",
+                2,
+                "indented attribute outside of a peripheral declaration"
+            ))
+        );
+        assert_eq!(
+            parse("x", "uart0: UART.PL011 @ sysbus <0x1000, +zz>\n").map(|_| ()),
+            Err(syntax("x", 1, "invalid address '<0x1000, +zz>'"))
+        );
+    }
+
+    #[test]
+    fn normalized_indentation() {
+        // As written in a TOML multi-line string
+        let config = "\n    using \"platforms/cpus/stm32f4.repl\"\n\n    phy3: Network.EthernetPhysicalLayer @ ethernet 3\n        Id1: 0x0000\n\n    cpu:\n        init:\n            Tag <0x40000000, 0x40000003> \"UNKNOWN\"\n    ";
+        let expected = "using \"platforms/cpus/stm32f4.repl\"\n\nphy3: Network.EthernetPhysicalLayer @ ethernet 3\n    Id1: 0x0000\n\ncpu:\n    init:\n        Tag <0x40000000, 0x40000003> \"UNKNOWN\"";
+        assert_eq!(normalize_indentation(config), expected);
+        assert!(parse("x", expected).is_ok());
+
+        // Starting on the line of the opening quotes
+        assert_eq!(
+            normalize_indentation("phy3: Network.EthernetPhysicalLayer @ ethernet 3\n        Id1: 0x0000\n    led: Miscellaneous.LED @ gpio 1\n        invert: true\n    "),
+            "phy3: Network.EthernetPhysicalLayer @ ethernet 3\n    Id1: 0x0000\nled: Miscellaneous.LED @ gpio 1\n    invert: true"
+        );
+        assert_eq!(
+            normalize_indentation("sysbus:\n        init:\n            Tag <0x0, 0x3> \"X\""),
+            "sysbus:\n        init:\n            Tag <0x0, 0x3> \"X\""
+        );
+    }
+}
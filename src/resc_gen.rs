@@ -1,5 +1,6 @@
 use crate::config::AppConfig;
 use crate::types::{PlatformDescriptionKind, RescDefinition};
+use crate::{semihosting, tail, timeout};
 use std::{fs, io::Write, path::Path};
 
 pub struct RescGen<'a, T: Write> {
@@ -19,11 +20,13 @@ impl<'a, T: Write> RescGen<'a, T> {
         resc: &RescDefinition,
     ) -> Result<(), Box<dyn std::error::Error>> {
         for p in resc.platform_descriptions.iter() {
-            if let PlatformDescriptionKind::GeneratedLocalFile(file_name) = p.kind() {
-                let out_path = output_dir.as_ref().join(file_name);
-                fs::write(out_path, p.content())?;
+            for (file_name, content) in p.generated_files() {
+                fs::write(output_dir.as_ref().join(file_name), content)?;
             }
         }
+        for a in resc.assets.iter() {
+            a.generate(output_dir.as_ref())?;
+        }
 
         let w = &mut self.writer;
         writeln!(w, ":name: {}", resc.name)?;
@@ -43,6 +46,16 @@ impl<'a, T: Write> RescGen<'a, T> {
         writeln!(w, "mach create \"{}\"", resc.machine_name)?;
         writeln!(w)?;
 
+        if let Some(logging) = resc.logging.as_ref() {
+            let cmds = logging.resc_fmt_machine();
+            for c in cmds.iter() {
+                writeln!(w, "{c}")?;
+            }
+            if !cmds.is_empty() {
+                writeln!(w)?;
+            }
+        }
+
         for c in resc.init_commands.iter() {
             writeln!(w, "{c}")?;
         }
@@ -50,6 +63,14 @@ impl<'a, T: Write> RescGen<'a, T> {
             writeln!(w)?;
         }
 
+        for a in resc.assets.iter() {
+            writeln!(
+                w,
+                "${} = @{}",
+                a.variable_name(),
+                a.output_path(output_dir.as_ref()).display()
+            )?;
+        }
         for v in resc.variables.iter() {
             writeln!(w, "{v}")?;
         }
@@ -71,6 +92,61 @@ impl<'a, T: Write> RescGen<'a, T> {
         }
         writeln!(w)?;
 
+        if let Some(sh) = resc.semihosting.as_ref() {
+            writeln!(
+                w,
+                "machine LoadPlatformDescriptionFromString \"semihosting: UART.SemihostingUart @ {}\"",
+                sh.cpu_local_name()
+            )?;
+            writeln!(
+                w,
+                "{} CreateFileBackend @{} true",
+                sh.uart(),
+                output_dir
+                    .as_ref()
+                    .join(semihosting::OUTPUT_FILE_NAME)
+                    .display()
+            )?;
+            let hook = semihosting::exit_hook(output_dir.as_ref());
+            for addr in sh.breakpoints.iter() {
+                writeln!(w, "{} AddHook 0x{addr:X}\n\"\"\"\n{hook}\n\"\"\"", sh.cpu)?;
+            }
+            writeln!(w)?;
+        }
+
+        for uart in resc.uart_captures.iter() {
+            writeln!(
+                w,
+                "{uart} CreateFileBackend @{} true",
+                tail::uart_output_path(output_dir.as_ref(), uart).display()
+            )?;
+        }
+        if !resc.uart_captures.is_empty() {
+            writeln!(w)?;
+        }
+
+        for t in resc.socket_terminals.iter() {
+            writeln!(w, "{}", t.resc_fmt())?;
+        }
+        if !resc.socket_terminals.is_empty() {
+            writeln!(w)?;
+        }
+
+        if let Some(logging) = resc.logging.as_ref() {
+            let cmds = logging.resc_fmt_peripherals();
+            for c in cmds.iter() {
+                writeln!(w, "{c}")?;
+            }
+            if !cmds.is_empty() {
+                writeln!(w)?;
+            }
+        }
+
+        if let Some(gdb) = resc.gdb.as_ref() {
+            writeln!(w, "{}", gdb.resc_fmt())?;
+            writeln!(w)?;
+        }
+
         for c in resc.pre_start_commands.iter() {
             writeln!(w, "{c}")?;
         }
@@ -84,13 +160,30 @@ impl<'a, T: Write> RescGen<'a, T> {
         writeln!(w, "runMacro $reset")?;
         writeln!(w)?;
 
-        if !app.omit_start {
-            let start_cmd = resc.start.as_deref().unwrap_or("start");
-            writeln!(w, "{}", start_cmd)?;
-            writeln!(w)?;
+        // The GDB server starts the emulation when the debugger attaches
+        let autostart_emulation = resc
+            .gdb
+            .as_ref()
+            .map(|g| g.autostart_emulation)
+            .unwrap_or(false);
+        if !app.omit_start && !autostart_emulation {
+            if let Some(t) = resc.virtual_timeout {
+                // Running for a fixed amount of emulated time also starts the emulation
+                for c in resc.post_start_commands.iter() {
+                    writeln!(w, "{c}")?;
+                }
+                if !resc.post_start_commands.is_empty() {
+                    writeln!(w)?;
+                }
 
-            for c in resc.post_start_commands.iter() {
-                writeln!(w, "{c}")?;
+                writeln!(w, "{}", timeout::run_for_resc_fmt(t, output_dir.as_ref()))?;
+            } else {
+                writeln!(w, "{}", resc.start.as_deref().unwrap_or("start"))?;
+                writeln!(w)?;
+
+                for c in resc.post_start_commands.iter() {
+                    writeln!(w, "{c}")?;
+                }
             }
         }
 
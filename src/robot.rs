@@ -0,0 +1,322 @@
+use crate::config::ExpectConfig;
+use crate::envsub::{envsub, EnvSubError};
+use crate::timeout::{self, TimeoutError};
+use crate::types::PostStartCommand;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{
+    fmt::{self, Write},
+    fs,
+    path::Path,
+    time::Duration,
+};
+
+/// The generated robot suite is written here, relative to the output directory
+pub const SUITE_FILE_NAME: &str = "emulate.robot";
+
+/// renode-test writes its results here, relative to the output directory
+pub const RESULTS_DIR_NAME: &str = "robot-results";
+
+/// renode-test's robot output, relative to the results directory
+pub const OUTPUT_FILE_NAME: &str = "robot_output.xml";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum RobotError {
+    #[error("At least one expectation is required to generate a robot suite")]
+    MissingExpectations,
+    #[error("The field '{_0}' cannot contain an empty string")]
+    Empty(&'static str),
+    #[error("The 'virtual-timeout' can't be used with a robot suite, use the expectations' timeouts instead")]
+    VirtualTimeout,
+    #[error(transparent)]
+    Timeout(#[from] TimeoutError),
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// A line the application is expected to print on a UART
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Expectation {
+    pub uart: String,
+    pub line: String,
+    pub timeout: Option<Duration>,
+}
+
+impl Expectation {
+    pub fn new(cfg: &ExpectConfig) -> Result<Self, RobotError> {
+        let uart = envsub(cfg.uart.trim())?;
+        if uart.is_empty() {
+            return Err(RobotError::Empty("expect.uart"));
+        }
+        let line = envsub(&cfg.line)?;
+        if line.is_empty() {
+            return Err(RobotError::Empty("expect.line"));
+        }
+        let timeout = cfg
+            .timeout
+            .as_ref()
+            .map(|t| timeout::parse_duration(&envsub(t)?).map_err(RobotError::from))
+            .transpose()?;
+        Ok(Self {
+            uart,
+            line,
+            timeout,
+        })
+    }
+}
+
+/// Generates a Robot Framework suite that runs the script and waits for each of the
+/// expected lines, in order.
+/// The script shouldn't start the emulation, the suite runs the start and post-start
+/// commands once its terminal testers are created.
+pub fn suite<P: AsRef<Path>>(
+    name: &str,
+    resc: P,
+    start: Option<&str>,
+    post_start_commands: &[PostStartCommand],
+    expectations: &[Expectation],
+) -> Result<String, RobotError> {
+    if expectations.is_empty() {
+        return Err(RobotError::MissingExpectations);
+    }
+
+    let mut uarts: Vec<&str> = Vec::new();
+    for e in expectations.iter() {
+        if !uarts.contains(&e.uart.as_str()) {
+            uarts.push(&e.uart);
+        }
+    }
+
+    let mut s = String::new();
+    // Infallible for String
+    let _ = writeln!(s, "*** Settings ***");
+    let _ = writeln!(s, "Suite Setup                   Setup");
+    let _ = writeln!(s, "Suite Teardown                Teardown");
+    let _ = writeln!(s, "Test Teardown                 Test Teardown");
+    let _ = writeln!(s, "Resource                      ${{RENODEKEYWORDS}}");
+    let _ = writeln!(s);
+    let _ = writeln!(s, "*** Test Cases ***");
+    let _ = writeln!(s, "{}", escape(name));
+    let _ = writeln!(
+        s,
+        "    Execute Script            {}",
+        escape(&resc.as_ref().display().to_string())
+    );
+    for (i, uart) in uarts.iter().enumerate() {
+        let _ = writeln!(
+            s,
+            "    ${{tester_{i}}}=              Create Terminal Tester    {}",
+            escape(uart)
+        );
+    }
+    if let Some(start) = start {
+        let _ = writeln!(s, "    Execute Command           {}", escape(start));
+    } else {
+        let _ = writeln!(s, "    Start Emulation");
+    }
+    for c in post_start_commands.iter() {
+        let _ = writeln!(s, "    Execute Command           {}", escape(c));
+    }
+    for e in expectations.iter() {
+        // SAFETY: every expectation's UART was collected above
+        let tester = uarts.iter().position(|u| *u == e.uart).unwrap();
+        let _ = write!(s, "    Wait For Line On Uart     {}", escape(&e.line));
+        if let Some(t) = e.timeout {
+            let _ = write!(s, "    timeout={}", t.as_secs_f64());
+        }
+        let _ = writeln!(s, "    testerId=${{tester_{tester}}}");
+    }
+    Ok(s)
+}
+
+/// The test results renode-test reported
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// The names of the failed tests
+    pub failures: Vec<String>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} failed, {} skipped",
+            self.passed, self.failed, self.skipped
+        )?;
+        for name in self.failures.iter() {
+            write!(f, "\n  FAILED {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the summary from the robot output in the results directory, if it was written
+pub fn summary<P: AsRef<Path>>(results_dir: P) -> Option<Summary> {
+    let xml = fs::read_to_string(results_dir.as_ref().join(OUTPUT_FILE_NAME)).ok()?;
+    parse_summary(&xml)
+}
+
+fn parse_summary(xml: &str) -> Option<Summary> {
+    lazy_static! {
+        static ref TOTAL_RE: Regex = Regex::new(r#"<total>\s*<stat ([^>]*)>"#)
+            .expect("Could not construct robot total Regex");
+        static ref TEST_RE: Regex = Regex::new(r#"(?s)<test [^>]*?name="([^"]*)".*?</test>"#)
+            .expect("Could not construct robot test Regex");
+        static ref STATUS_RE: Regex = Regex::new(r#"<status status="([A-Z]+)""#)
+            .expect("Could not construct robot status Regex");
+    }
+    let stat = TOTAL_RE.captures(xml)?.get(1)?.as_str();
+    let count = |attr: &str| {
+        Regex::new(&format!(r#"\b{attr}="(\d+)""#))
+            .ok()
+            .and_then(|re| re.captures(stat))
+            .and_then(|c| c[1].parse().ok())
+            .unwrap_or(0)
+    };
+    // A test's own status follows those of its keywords
+    let failures = TEST_RE
+        .captures_iter(xml)
+        .filter(|c| {
+            STATUS_RE
+                .captures_iter(&c[0])
+                .last()
+                .is_some_and(|s| &s[1] == "FAIL")
+        })
+        .map(|c| xml_unescape(&c[1]))
+        .collect();
+    Some(Summary {
+        passed: count("pass"),
+        failed: count("fail"),
+        skipped: count("skip"),
+        failures,
+    })
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Escapes robot's variable syntax, backslashes, newlines and the spaces it would treat as separators
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let chars: Vec<char> = s.chars().collect();
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '$' | '@' | '&' | '%' if chars.get(i + 1) == Some(&'{') => {
+                out.push('\\');
+                out.push(*c);
+            }
+            ' ' if i == 0 || i == chars.len() - 1 || chars.get(i - 1) == Some(&' ') => {
+                out.push_str("${SPACE}")
+            }
+            _ => out.push(*c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("boot ok"), "boot ok");
+        assert_eq!(escape("a  b"), "a ${SPACE}b");
+        assert_eq!(escape(" x "), "${SPACE}x${SPACE}");
+        assert_eq!(escape("${HOME} \\n"), "\\${HOME} \\\\n");
+        assert_eq!(escape("a\nb"), "a\\nb");
+    }
+
+    #[test]
+    fn robot_suite() {
+        let expectations = vec![
+            Expectation {
+                uart: "sysbus.usart3".to_owned(),
+                line: "boot ok".to_owned(),
+                timeout: Some(Duration::from_secs(2)),
+            },
+            Expectation {
+                uart: "sysbus.usart2".to_owned(),
+                line: "ready".to_owned(),
+                timeout: None,
+            },
+        ];
+        let s = suite("my-system", "/out/emulate.resc", None, &[], &expectations).unwrap();
+        assert!(s.contains("    Execute Script            /out/emulate.resc\n"));
+        assert!(
+            s.contains("    ${tester_0}=              Create Terminal Tester    sysbus.usart3\n")
+        );
+        assert!(
+            s.contains("    ${tester_1}=              Create Terminal Tester    sysbus.usart2\n")
+        );
+        assert!(s.contains(
+            "    Wait For Line On Uart     boot ok    timeout=2    testerId=${tester_0}\n"
+        ));
+        assert!(s.contains("    Wait For Line On Uart     ready    testerId=${tester_1}\n"));
+        assert!(s.contains("    Start Emulation\n"));
+
+        let post_start = vec![PostStartCommand::new("sysbus.usart3 WriteChar 0x41").unwrap()];
+        let s = suite(
+            "my-system",
+            "/out/emulate.resc",
+            Some("emulation RunFor \"00:00:01\""),
+            &post_start,
+            &expectations,
+        )
+        .unwrap();
+        assert!(!s.contains("Start Emulation"));
+        assert!(s.contains(
+            "    Execute Command           emulation RunFor \"00:00:01\"\n    Execute Command           sysbus.usart3 WriteChar 0x41\n"
+        ));
+        assert_eq!(
+            suite("my-system", "/out/emulate.resc", None, &[], &[]),
+            Err(RobotError::MissingExpectations)
+        );
+    }
+
+    #[test]
+    fn robot_summary() {
+        let xml = r#"<robot>
+<suite name="Emulate">
+<test id="s1-t1" name="boot &amp; run" line="7">
+<kw name="Wait For Line On Uart"><status status="FAIL" start="x" elapsed="2"/></kw>
+<status status="FAIL" start="x" elapsed="2">timeout</status>
+</test>
+<test id="s1-t2" name="ready" line="9">
+<kw name="Wait For Line On Uart"><status status="PASS" start="x" elapsed="1"/></kw>
+<status status="PASS" start="x" elapsed="1"/>
+</test>
+</suite>
+<statistics>
+<total>
+<stat pass="1" fail="1" skip="0">All Tests</stat>
+</total>
+</statistics>
+</robot>"#;
+        let summary = parse_summary(xml).unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                passed: 1,
+                failed: 1,
+                skipped: 0,
+                failures: vec!["boot & run".to_owned()],
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "1 passed, 1 failed, 0 skipped\n  FAILED boot & run"
+        );
+        assert_eq!(parse_summary("<robot/>"), None);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::config::RttConfig;
+use crate::envsub::{envsub, EnvSubError};
+use crate::monitor::{self, MonitorClient, MonitorError};
+use std::{
+    io::{self, Write},
+    process::{Child, Command, Stdio},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const CONTROL_BLOCK_ID: &[u8] = b"SEGGER RTT";
+/// acID[16] + MaxNumUpBuffers + MaxNumDownBuffers
+const CONTROL_BLOCK_HEADER_SIZE: u64 = 24;
+/// sName + pBuffer + SizeOfBuffer + WrOff + RdOff + Flags
+const BUFFER_DESC_SIZE: u64 = 24;
+const BUFFER_DESC_RD_OFF_OFFSET: u64 = 16;
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum RttError {
+    #[error("RTT requires the renode monitor 'port' to be configured")]
+    MissingMonitorPort,
+    #[error("The RTT control block symbol '{_0}' could not be found in the ELF file")]
+    ControlBlockSymbolNotFound(String),
+    #[error("The RTT decoder command for up-channel {_0} is empty")]
+    EmptyDecoder(u32),
+    #[error("Failed to start the RTT decoder '{_0}'. {_1}")]
+    DecoderSpawn(String, String),
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// Streams RTT up-channels from the emulated target by polling
+/// the control block and ring buffers through the renode monitor.
+pub struct RttReader {
+    port: u16,
+    machine_name: String,
+    control_block_address: u64,
+    poll_interval: Duration,
+    channels: Vec<UpChannel>,
+}
+
+struct UpChannel {
+    index: u32,
+    decoder: Option<String>,
+    output: Box<dyn Write + Send>,
+    sink: Box<dyn Write + Send>,
+    decoder_process: Option<(Child, JoinHandle<()>)>,
+}
+
+impl RttReader {
+    pub fn new(
+        cfg: &RttConfig,
+        port: u16,
+        machine_name: &str,
+        control_block_address: u64,
+    ) -> Result<Self, RttError> {
+        let mut channels = Vec::new();
+        for ch in cfg.up_channels.iter() {
+            let decoder = ch.decoder.as_ref().map(|d| envsub(d)).transpose()?;
+            if decoder.as_ref().is_some_and(|d| d.trim().is_empty()) {
+                return Err(RttError::EmptyDecoder(ch.channel));
+            }
+            channels.push(UpChannel {
+                index: ch.channel,
+                decoder,
+                output: Box::new(io::stdout()),
+                sink: Box::new(io::sink()),
+                decoder_process: None,
+            });
+        }
+
+        Ok(RttReader {
+            port,
+            machine_name: machine_name.to_owned(),
+            control_block_address,
+            poll_interval: Duration::from_millis(cfg.poll_interval_ms),
+            channels,
+        })
+    }
+
+    /// Send the (decoded) output of an up-channel somewhere other than stdout.
+    /// Returns false if the channel isn't configured.
+    pub fn set_output(&mut self, channel: u32, output: Box<dyn Write + Send>) -> bool {
+        if let Some(ch) = self.channels.iter_mut().find(|ch| ch.index == channel) {
+            ch.output = output;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Poll in a background thread until the monitor connection closes,
+    /// which happens when renode exits.
+    pub fn spawn(mut self) -> Result<JoinHandle<()>, RttError> {
+        for ch in self.channels.iter_mut() {
+            let output = std::mem::replace(&mut ch.output, Box::new(io::sink()));
+            if let Some(decoder) = ch.decoder.as_ref() {
+                let mut args = decoder.split_whitespace();
+                // SAFETY: decoders are checked to be non-empty
+                let program = args.next().unwrap();
+                log::debug!("Using RTT decoder '{decoder}' for up-channel {}", ch.index);
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| RttError::DecoderSpawn(decoder.clone(), e.to_string()))?;
+                // SAFETY: stdin and stdout were configured to be piped
+                ch.sink = Box::new(child.stdin.take().unwrap());
+                let mut stdout = child.stdout.take().unwrap();
+                let copier = thread::spawn(move || {
+                    let mut output = output;
+                    let _ = io::copy(&mut stdout, &mut output);
+                    let _ = output.flush();
+                });
+                ch.decoder_process = Some((child, copier));
+            } else {
+                ch.sink = output;
+            }
+        }
+
+        Ok(thread::spawn(move || {
+            if let Err(e) = self.run() {
+                log::debug!("RTT reader stopped. {e}");
+            }
+            for ch in self.channels.iter_mut() {
+                let _ = ch.sink.flush();
+                // Closes the decoder's stdin
+                ch.sink = Box::new(io::sink());
+                if let Some((mut child, copier)) = ch.decoder_process.take() {
+                    let _ = child.wait();
+                    let _ = copier.join();
+                }
+            }
+        }))
+    }
+
+    fn run(&mut self) -> Result<(), MonitorError> {
+        let mut mon = MonitorClient::connect(self.port, monitor::CONNECT_TIMEOUT)?;
+        mon.select_machine(&self.machine_name)?;
+
+        let cb = self.control_block_address;
+        log::debug!("Waiting for the RTT control block at 0x{cb:X}");
+        let max_up_buffers = loop {
+            let header = mon.read_bytes(cb, CONTROL_BLOCK_HEADER_SIZE as usize)?;
+            if header.starts_with(CONTROL_BLOCK_ID) {
+                break u32::from_le_bytes([header[16], header[17], header[18], header[19]]);
+            }
+            thread::sleep(self.poll_interval);
+        };
+        log::debug!("Found RTT control block with {max_up_buffers} up-channels");
+
+        for ch in self.channels.iter() {
+            if ch.index >= max_up_buffers {
+                log::warn!(
+                    "RTT up-channel {} doesn't exist, the target has {max_up_buffers}",
+                    ch.index
+                );
+            }
+        }
+        self.channels.retain(|ch| ch.index < max_up_buffers);
+
+        loop {
+            for ch in self.channels.iter_mut() {
+                let desc_addr =
+                    cb + CONTROL_BLOCK_HEADER_SIZE + u64::from(ch.index) * BUFFER_DESC_SIZE;
+                let desc_bytes = mon.read_bytes(desc_addr, BUFFER_DESC_SIZE as usize)?;
+                let desc = BufferDesc::from_bytes(&desc_bytes).ok_or_else(|| {
+                    MonitorError::UnexpectedResponse(format!("{desc_bytes:02X?}"))
+                })?;
+
+                let mut rd_off = desc.rd_off;
+                for (offset, len) in desc.pending() {
+                    let data = mon.read_bytes(u64::from(desc.buffer + offset), len as usize)?;
+                    ch.sink.write_all(&data)?;
+                    rd_off = (offset + len) % desc.size;
+                }
+                if rd_off != desc.rd_off {
+                    ch.sink.flush()?;
+                    mon.write_double_word(desc_addr + BUFFER_DESC_RD_OFF_OFFSET, rd_off)?;
+                }
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct BufferDesc {
+    buffer: u32,
+    size: u32,
+    wr_off: u32,
+    rd_off: u32,
+}
+
+impl BufferDesc {
+    fn from_bytes(b: &[u8]) -> Option<Self> {
+        let word = |i: usize| -> Option<u32> {
+            Some(u32::from_le_bytes(
+                b.get(i * 4..i * 4 + 4)?.try_into().ok()?,
+            ))
+        };
+        Some(BufferDesc {
+            buffer: word(1)?,
+            size: word(2)?,
+            wr_off: word(3)?,
+            rd_off: word(4)?,
+        })
+    }
+
+    /// The (offset, length) regions of the ring buffer that contain unread data, in order.
+    fn pending(&self) -> Vec<(u32, u32)> {
+        if self.size == 0 || self.wr_off >= self.size || self.rd_off >= self.size {
+            Vec::new()
+        } else if self.wr_off >= self.rd_off {
+            vec![(self.rd_off, self.wr_off - self.rd_off)]
+        } else {
+            vec![(self.rd_off, self.size - self.rd_off), (0, self.wr_off)]
+        }
+        .into_iter()
+        .filter(|(_, len)| *len != 0)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desc(size: u32, wr_off: u32, rd_off: u32) -> BufferDesc {
+        BufferDesc {
+            buffer: 0x2000_0000,
+            size,
+            wr_off,
+            rd_off,
+        }
+    }
+
+    #[test]
+    fn buffer_desc_from_bytes() {
+        let bytes = [
+            0x00, 0x01, 0x00, 0x20, // sName
+            0x00, 0x02, 0x00, 0x20, // pBuffer
+            0x00, 0x04, 0x00, 0x00, // SizeOfBuffer
+            0x10, 0x00, 0x00, 0x00, // WrOff
+            0x08, 0x00, 0x00, 0x00, // RdOff
+            0x02, 0x00, 0x00, 0x00, // Flags
+        ];
+        assert_eq!(
+            BufferDesc::from_bytes(&bytes),
+            Some(BufferDesc {
+                buffer: 0x2000_0200,
+                size: 1024,
+                wr_off: 16,
+                rd_off: 8,
+            })
+        );
+        assert_eq!(BufferDesc::from_bytes(&bytes[..12]), None);
+    }
+
+    #[test]
+    fn buffer_desc_pending() {
+        assert_eq!(desc(64, 8, 8).pending(), vec![]);
+        assert_eq!(desc(64, 16, 8).pending(), vec![(8, 8)]);
+        assert_eq!(desc(64, 4, 60).pending(), vec![(60, 4), (0, 4)]);
+        assert_eq!(desc(64, 0, 60).pending(), vec![(60, 4)]);
+        assert_eq!(desc(64, 80, 0).pending(), vec![]);
+    }
+}
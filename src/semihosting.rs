@@ -0,0 +1,90 @@
+use std::{fs, path::Path};
+
+/// Thumb encoding of `BKPT 0xAB`, the Cortex-M semihosting trap
+pub const BKPT_SEMIHOSTING: u16 = 0xBEAB;
+
+/// The console output of the semihosting UART is written here, relative to the output directory
+pub const OUTPUT_FILE_NAME: &str = "semihosting.out";
+
+/// The `SYS_EXIT` status is written here, relative to the output directory
+pub const EXIT_STATUS_FILE_NAME: &str = "semihosting.exit";
+
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+
+/// Python hook for each semihosting breakpoint.
+/// Renode doesn't handle `SYS_EXIT`, so the hook records the
+/// reason and subcode for renode-run and exits the emulator.
+pub(crate) fn exit_hook<P: AsRef<Path>>(output_dir: P) -> String {
+    let status_path = output_dir
+        .as_ref()
+        .join(EXIT_STATUS_FILE_NAME)
+        .display()
+        .to_string()
+        .replace('\\', "/")
+        .replace('\'', "\\'");
+    format!(
+        "\
+op = self.GetRegisterUnsafe(0).RawValue
+arg = self.GetRegisterUnsafe(1).RawValue
+if op == 0x{SYS_EXIT:X} or op == 0x{SYS_EXIT_EXTENDED:X}:
+    if op == 0x{SYS_EXIT_EXTENDED:X}:
+        reason = machine.SystemBus.ReadDoubleWord(arg)
+        subcode = machine.SystemBus.ReadDoubleWord(arg + 4)
+    else:
+        reason = arg
+        subcode = 0
+    f = open('{status_path}', 'w')
+    f.write('%d %d' % (reason, subcode))
+    f.close()
+    Antmicro.Renode.Emulator.Exit()"
+    )
+}
+
+/// Returns the exit code the application requested with `SYS_EXIT`, if it did
+pub fn exit_code<P: AsRef<Path>>(output_dir: P) -> Option<i32> {
+    let status = fs::read_to_string(output_dir.as_ref().join(EXIT_STATUS_FILE_NAME)).ok()?;
+    let mut fields = status.split_whitespace().map(|f| f.parse::<u32>().ok());
+    let reason = fields.next()??;
+    let subcode = fields.next()??;
+    Some(exit_status_to_code(reason, subcode))
+}
+
+fn exit_status_to_code(reason: u32, subcode: u32) -> i32 {
+    if reason == ADP_STOPPED_APPLICATION_EXIT {
+        subcode as i32
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(exit_code(dir.path()), None);
+
+        fs::write(dir.path().join(EXIT_STATUS_FILE_NAME), "131110 0").unwrap();
+        assert_eq!(exit_code(dir.path()), Some(0));
+
+        fs::write(dir.path().join(EXIT_STATUS_FILE_NAME), "131110 3").unwrap();
+        assert_eq!(exit_code(dir.path()), Some(3));
+
+        // ADP_Stopped_RunTimeErrorUnknown
+        fs::write(dir.path().join(EXIT_STATUS_FILE_NAME), "131107 0").unwrap();
+        assert_eq!(exit_code(dir.path()), Some(1));
+
+        fs::write(dir.path().join(EXIT_STATUS_FILE_NAME), "garbage").unwrap();
+        assert_eq!(exit_code(dir.path()), None);
+    }
+
+    #[test]
+    fn exit_hook_paths() {
+        let hook = exit_hook("/tmp/it's here");
+        assert!(hook.contains("open('/tmp/it\\'s here/semihosting.exit', 'w')"));
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Where the file backend of a captured UART is written, relative to the output directory
+pub fn uart_output_path<P: AsRef<Path>>(output_dir: P, uart: &str) -> PathBuf {
+    output_dir.as_ref().join(format!("uart-{uart}.out"))
+}
+
+/// Follows a file that renode writes to (i.e. a UART file backend),
+/// copying everything appended to it into a sink.
+pub struct FileTail {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl FileTail {
+    pub fn spawn<P: AsRef<Path>>(path: P, mut sink: Box<dyn Write + Send>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            if let Err(e) = follow(&path, sink.as_mut(), &thread_stop) {
+                log::warn!("Stopped following '{}'. {e}", path.display());
+            }
+            let _ = sink.flush();
+        });
+        FileTail { stop, handle }
+    }
+
+    /// Copies whatever is left in the file and waits for the thread to finish
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let _ = self.handle.join();
+    }
+}
+
+fn follow(path: &PathBuf, sink: &mut dyn Write, stop: &AtomicBool) -> io::Result<()> {
+    let mut file = loop {
+        match File::open(path) {
+            Ok(f) => break f,
+            Err(_) if stop.load(Ordering::SeqCst) => return Ok(()),
+            Err(_) => thread::sleep(POLL_INTERVAL),
+        }
+    };
+
+    let mut buf = [0_u8; 4096];
+    loop {
+        // Check before reading so the final read drains the file
+        let stopping = stop.load(Ordering::SeqCst);
+        let n = file.read(&mut buf)?;
+        if n != 0 {
+            sink.write_all(&buf[..n])?;
+            sink.flush()?;
+        } else if stopping {
+            return Ok(());
+        } else {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
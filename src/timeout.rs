@@ -0,0 +1,142 @@
+use std::{
+    io,
+    path::Path,
+    process::{Child, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// renode-run's exit code when either of the timeouts fire, same as coreutils' `timeout`
+pub const EXIT_CODE: i32 = 124;
+
+/// Created by the generated script when the virtual timeout fires, relative to the output directory
+pub const VIRTUAL_TIMEOUT_MARKER_FILE_NAME: &str = "virtual-timeout";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long renode has to exit after being asked to quit
+const QUIT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum TimeoutError {
+    #[error("Invalid duration '{_0}', expected a number with an optional unit (h, m, s, ms, us), e.g. '30s'")]
+    InvalidDuration(String),
+}
+
+/// Parses durations like `30s`, `1.5s`, `500ms`, `2m` or `1h`. Plain numbers are seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, TimeoutError> {
+    let err = || TimeoutError::InvalidDuration(s.to_owned());
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let value: f64 = value.parse().map_err(|_| err())?;
+    let secs = match unit.trim() {
+        "" | "s" => value,
+        "ms" => value / 1_000.0,
+        "us" => value / 1_000_000.0,
+        "m" => value * 60.0,
+        "h" => value * 60.0 * 60.0,
+        _ => return Err(err()),
+    };
+    Duration::try_from_secs_f64(secs).map_err(|_| err())
+}
+
+/// Renode script lines that run the emulation for `duration` of virtual time,
+/// then record that the timeout fired and quit
+pub(crate) fn run_for_resc_fmt<P: AsRef<Path>>(duration: Duration, output_dir: P) -> String {
+    let marker_path = output_dir
+        .as_ref()
+        .join(VIRTUAL_TIMEOUT_MARKER_FILE_NAME)
+        .display()
+        .to_string()
+        .replace('\\', "/")
+        .replace('\'', "\\'");
+    let secs = duration.as_secs();
+    format!(
+        "emulation RunFor \"{:02}:{:02}:{:02}.{:06}\"\npython\n\"\"\"\nopen('{marker_path}', 'w').close()\n\"\"\"\nquit",
+        secs / 3600,
+        (secs / 60) % 60,
+        secs % 60,
+        duration.subsec_micros()
+    )
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum WaitOutcome {
+    Exited(ExitStatus),
+    TimedOut,
+    Stopped,
+}
+
+/// Waits for the child to exit, ending it if it's still running after `timeout`
+/// or once `stop` returns true. `quit` asks the child to exit, returning false if
+/// it couldn't, in which case or if the child doesn't exit in time it's killed.
+pub fn wait(
+    child: &mut Child,
+    timeout: Option<Duration>,
+    mut stop: impl FnMut() -> bool,
+    quit: impl FnOnce() -> bool,
+) -> io::Result<WaitOutcome> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(WaitOutcome::Exited(status));
+        }
+        let outcome = if deadline.is_some_and(|d| Instant::now() >= d) {
+            Some(WaitOutcome::TimedOut)
+        } else if stop() {
+            Some(WaitOutcome::Stopped)
+        } else {
+            None
+        };
+        if let Some(outcome) = outcome {
+            // Give renode the chance to exit on its own before it's killed
+            if quit() {
+                let grace_deadline = Instant::now() + QUIT_GRACE_PERIOD;
+                while Instant::now() < grace_deadline {
+                    if child.try_wait()?.is_some() {
+                        return Ok(outcome);
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+            child.kill()?;
+            child.wait()?;
+            return Ok(outcome);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 1.5s "), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("250us"), Ok(Duration::from_micros(250)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert_eq!(
+            parse_duration("10 days"),
+            Err(TimeoutError::InvalidDuration("10 days".to_owned()))
+        );
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn run_for() {
+        assert_eq!(
+            run_for_resc_fmt(Duration::from_millis(3_723_500), "/out"),
+            "emulation RunFor \"01:02:03.500000\"\npython\n\"\"\"\nopen('/out/virtual-timeout', 'w').close()\n\"\"\"\nquit"
+        );
+        assert!(run_for_resc_fmt(Duration::from_secs(1), "/it's")
+            .contains("open('/it\\'s/virtual-timeout', 'w')"));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::renode;
+use flate2::read::GzDecoder;
+use semver::{Version, VersionReq};
+use std::{
+    env,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+pub const TOOLCHAINS_DIR_ENV_VAR: &str = "RENODE_RUN_TOOLCHAINS_DIR";
+const DEFAULT_FILE_NAME: &str = "default";
+const RENODE_BIN_NAME: &str = "renode";
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum ToolchainError {
+    #[error("Could not determine the toolchains directory, set {TOOLCHAINS_DIR_ENV_VAR} or HOME")]
+    NoCacheDir,
+    #[error("Toolchain I/O error on '{_0}'. {_1}")]
+    Io(String, String),
+    #[error("Failed to unpack '{_0}'. {_1}")]
+    Unpack(String, String),
+    #[error("The archive '{_0}' doesn't contain a portable renode build")]
+    NotPortable(String),
+    #[error("Could not determine the renode version of '{_0}'")]
+    UnknownVersion(String),
+    #[error("Renode {_0} is already installed, use --force to replace it")]
+    AlreadyInstalled(String),
+    #[error("Renode {_0} is not installed")]
+    NotInstalled(String),
+    #[error("Invalid version '{_0}'")]
+    InvalidVersion(String),
+}
+
+/// An unpacked portable renode build
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Toolchain {
+    pub version: Version,
+    pub root: PathBuf,
+}
+
+impl Toolchain {
+    pub fn bin(&self) -> PathBuf {
+        self.root.join(RENODE_BIN_NAME)
+    }
+}
+
+/// The cache directory of unpacked portable renode builds, one per version
+pub struct Toolchains {
+    dir: PathBuf,
+}
+
+impl Toolchains {
+    /// Uses `RENODE_RUN_TOOLCHAINS_DIR`, `$XDG_CACHE_HOME/renode-run/toolchains`
+    /// or `~/.cache/renode-run/toolchains`
+    pub fn open() -> Result<Self, ToolchainError> {
+        let dir = if let Some(d) = env::var_os(TOOLCHAINS_DIR_ENV_VAR) {
+            PathBuf::from(d)
+        } else if let Some(d) = env::var_os("XDG_CACHE_HOME") {
+            PathBuf::from(d).join("renode-run").join("toolchains")
+        } else if let Some(home) = env::var_os("HOME") {
+            PathBuf::from(home)
+                .join(".cache")
+                .join("renode-run")
+                .join("toolchains")
+        } else {
+            return Err(ToolchainError::NoCacheDir);
+        };
+        Ok(Toolchains { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Unpacks a portable renode `.tar.gz` archive
+    pub fn add<P: AsRef<Path>>(
+        &self,
+        archive: P,
+        force: bool,
+    ) -> Result<Toolchain, ToolchainError> {
+        let archive = archive.as_ref();
+        let archive_name = archive.display().to_string();
+        fs::create_dir_all(&self.dir).map_err(|e| io_err(&self.dir, e))?;
+
+        let staging = tempfile::tempdir_in(&self.dir).map_err(|e| io_err(&self.dir, e))?;
+        let file = File::open(archive).map_err(|e| io_err(archive, e))?;
+        tar::Archive::new(GzDecoder::new(file))
+            .unpack(staging.path())
+            .map_err(|e| ToolchainError::Unpack(archive_name.clone(), e.to_string()))?;
+
+        // Portable archives contain a single top-level directory, e.g. renode_1.14.0_portable
+        let root = if staging.path().join(RENODE_BIN_NAME).is_file() {
+            staging.path().to_path_buf()
+        } else {
+            fs::read_dir(staging.path())
+                .map_err(|e| io_err(staging.path(), e))?
+                .flatten()
+                .map(|e| e.path())
+                .find(|p| p.join(RENODE_BIN_NAME).is_file())
+                .ok_or_else(|| ToolchainError::NotPortable(archive_name.clone()))?
+        };
+
+        let version = renode::version(&root.join(RENODE_BIN_NAME))
+            .ok()
+            .or_else(|| {
+                root.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(renode::parse_version)
+            })
+            .or_else(|| {
+                archive
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(renode::parse_version)
+            })
+            .ok_or_else(|| ToolchainError::UnknownVersion(archive_name.clone()))?;
+
+        let dest = self.dir.join(version.to_string());
+        if dest.exists() {
+            if !force {
+                return Err(ToolchainError::AlreadyInstalled(version.to_string()));
+            }
+            fs::remove_dir_all(&dest).map_err(|e| io_err(&dest, e))?;
+        }
+        fs::rename(&root, &dest).map_err(|e| io_err(&dest, e))?;
+
+        Ok(Toolchain {
+            version,
+            root: dest,
+        })
+    }
+
+    /// The installed toolchains, oldest first
+    pub fn list(&self) -> Vec<Toolchain> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut toolchains: Vec<Toolchain> = entries
+            .flatten()
+            .filter_map(|e| {
+                let version = Version::parse(e.file_name().to_str()?).ok()?;
+                let root = e.path();
+                root.join(RENODE_BIN_NAME)
+                    .is_file()
+                    .then_some(Toolchain { version, root })
+            })
+            .collect();
+        toolchains.sort();
+        toolchains
+    }
+
+    pub fn default_version(&self) -> Option<Version> {
+        let v = fs::read_to_string(self.dir.join(DEFAULT_FILE_NAME)).ok()?;
+        Version::parse(v.trim()).ok()
+    }
+
+    pub fn set_default(&self, version: &str) -> Result<Toolchain, ToolchainError> {
+        let version = Version::parse(version.trim())
+            .map_err(|_| ToolchainError::InvalidVersion(version.to_owned()))?;
+        let toolchain = self
+            .list()
+            .into_iter()
+            .find(|t| t.version == version)
+            .ok_or_else(|| ToolchainError::NotInstalled(version.to_string()))?;
+        let path = self.dir.join(DEFAULT_FILE_NAME);
+        fs::write(&path, version.to_string()).map_err(|e| io_err(&path, e))?;
+        Ok(toolchain)
+    }
+
+    /// The newest toolchain matching the requirement, or the default toolchain without one
+    pub fn select(&self, requirement: Option<&VersionReq>) -> Option<Toolchain> {
+        let toolchains = self.list();
+        match requirement {
+            Some(req) => toolchains
+                .into_iter()
+                .rev()
+                .find(|t| req.matches(&t.version)),
+            None => {
+                let default = self.default_version()?;
+                toolchains.into_iter().find(|t| t.version == default)
+            }
+        }
+    }
+}
+
+fn io_err(path: &Path, e: std::io::Error) -> ToolchainError {
+    ToolchainError::Io(path.display().to_string(), e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(toolchains: &Toolchains, version: &str) {
+        let root = toolchains.dir().join(version);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(RENODE_BIN_NAME), "").unwrap();
+    }
+
+    #[test]
+    fn selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let toolchains = Toolchains {
+            dir: dir.path().to_path_buf(),
+        };
+        assert_eq!(toolchains.select(None), None);
+
+        install(&toolchains, "1.13.3");
+        install(&toolchains, "1.14.0");
+        install(&toolchains, "1.15.3");
+        assert_eq!(toolchains.list().len(), 3);
+
+        let req = renode::parse_requirement(">=1.14, <1.15").unwrap();
+        assert_eq!(
+            toolchains.select(Some(&req)).map(|t| t.version),
+            Some(Version::new(1, 14, 0))
+        );
+        let req = renode::parse_requirement(">=1.14").unwrap();
+        assert_eq!(
+            toolchains.select(Some(&req)).map(|t| t.version),
+            Some(Version::new(1, 15, 3))
+        );
+
+        assert_eq!(toolchains.select(None), None);
+        toolchains.set_default("1.13.3").unwrap();
+        assert_eq!(
+            toolchains.select(None).map(|t| t.version),
+            Some(Version::new(1, 13, 3))
+        );
+        assert!(toolchains.set_default("1.12.0").is_err());
+    }
+}
@@ -1,7 +1,21 @@
-use crate::config::{AppConfig, RenodeScriptConfig};
+use crate::assets::{self, Asset, AssetError};
+use crate::config::{
+    AppConfig, GdbConfig, LoggingConfig, RenodeScriptConfig, SemihostingConfig,
+    SocketTerminalConfig,
+};
+use crate::elf::{ElfError, ElfFile};
 use crate::envsub::{envsub, EnvSubError};
+use crate::platforms;
+use crate::repl;
+use crate::semihosting::BKPT_SEMIHOSTING;
+use crate::timeout::{self, TimeoutError};
 use derive_more::{AsRef, Deref, Display, Into};
-use std::{fs, path::Path};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 use unindent::unindent;
 
 const REPL_FILE_EXT: &str = "repl";
@@ -16,10 +30,18 @@ pub struct RescDefinition {
     pub init_commands: Vec<InitCommand>,
     pub variables: Vec<Variable>,
     pub platform_descriptions: Vec<PlatformDescription>,
+    pub assets: Vec<Asset>,
     pub reset: ResetMacro,
     pub start: Option<String>,
     pub pre_start_commands: Vec<PreStartCommand>,
     pub post_start_commands: Vec<PostStartCommand>,
+    pub semihosting: Option<Semihosting>,
+    pub gdb: Option<GdbServer>,
+    pub socket_terminals: Vec<SocketTerminal>,
+    pub logging: Option<Logging>,
+    pub virtual_timeout: Option<Duration>,
+    /// UARTs whose output is written to a file backend for renode-run to consume
+    pub uart_captures: Vec<String>,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
@@ -30,16 +52,30 @@ pub enum RescDefinitionError {
     RescGenericField(#[from] RescGenericFieldError),
     #[error(transparent)]
     PlatformDescription(#[from] PlatformDescriptionError),
+    #[error(transparent)]
+    Asset(#[from] AssetError),
     #[error("At least one platform description is required")]
     MissingPlatformDescription,
     #[error("The application executable file '{_0}' could not be found")]
     ExeNotFound(String),
+    #[error(transparent)]
+    Elf(#[from] ElfError),
+    #[error(transparent)]
+    Logging(#[from] LoggingError),
+    #[error(transparent)]
+    Timeout(#[from] TimeoutError),
+    #[error("The port '{_0}' is \"auto\" but wasn't allocated")]
+    UnallocatedPort(&'static str),
+    #[error("The 'start' command can't be combined with 'virtual-timeout', which starts the emulation itself")]
+    StartWithVirtualTimeout,
+    #[error("'virtual-timeout' runs the emulation, so it can't be combined with '{_0}', which leaves it stopped")]
+    StoppedWithVirtualTimeout(&'static str),
 }
 
 impl RescDefinition {
     pub fn new<P: AsRef<Path>>(
         resc: &RenodeScriptConfig,
-        _app: &AppConfig,
+        app: &AppConfig,
         bin_var_value: P,
     ) -> Result<Self, RescDefinitionError> {
         if !bin_var_value.as_ref().exists() {
@@ -48,12 +84,35 @@ impl RescDefinition {
             ));
         }
 
-        let mut platform_descriptions = Vec::new();
-        if let Some(p) = resc.platform_description.as_ref() {
-            platform_descriptions.push(PlatformDescription::new(p.as_str())?);
-        }
-        for p in resc.platform_descriptions.iter() {
-            platform_descriptions.push(PlatformDescription::new(p.as_str())?);
+        let semihosting = resc
+            .semihosting
+            .as_ref()
+            .map(|s| Semihosting::new(s, bin_var_value.as_ref()))
+            .transpose()?;
+
+        let gdb = resc.gdb.as_ref().map(GdbServer::new).transpose()?;
+
+        let socket_terminals = resc
+            .socket_terminals
+            .iter()
+            .map(SocketTerminal::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let logging = resc.logging.as_ref().map(Logging::new).transpose()?;
+
+        let assets = assets::resolve(&resc.assets)?;
+
+        // Every generated file shares the output directory
+        let mut reserved: Vec<String> = assets.iter().map(|a| a.file_name.clone()).collect();
+        let mut platform_descriptions: Vec<PlatformDescription> = Vec::new();
+        for p in resc
+            .platform_description
+            .iter()
+            .chain(resc.platform_descriptions.iter())
+        {
+            let desc = PlatformDescription::new(p.as_str(), &reserved)?;
+            reserved.extend(desc.imports().iter().map(|i| i.file_name.clone()));
+            platform_descriptions.push(desc);
         }
 
         if platform_descriptions.is_empty() {
@@ -107,6 +166,23 @@ impl RescDefinition {
 
         let start = resc.start.clone();
 
+        let virtual_timeout = resc
+            .virtual_timeout
+            .as_ref()
+            .map(|t| timeout::parse_duration(&envsub(t)?).map_err(RescDefinitionError::from))
+            .transpose()?;
+        if virtual_timeout.is_some() {
+            if start.is_some() {
+                return Err(RescDefinitionError::StartWithVirtualTimeout);
+            } else if app.omit_start {
+                return Err(RescDefinitionError::StoppedWithVirtualTimeout("omit-start"));
+            } else if gdb.as_ref().is_some_and(|g| g.autostart_emulation) {
+                return Err(RescDefinitionError::StoppedWithVirtualTimeout(
+                    "gdb.autostart-emulation",
+                ));
+            }
+        }
+
         Ok(RescDefinition {
             name,
             description,
@@ -114,10 +190,17 @@ impl RescDefinition {
             init_commands,
             variables,
             platform_descriptions,
+            assets,
             reset,
             start,
             pre_start_commands,
             post_start_commands,
+            semihosting,
+            gdb,
+            socket_terminals,
+            logging,
+            virtual_timeout,
+            uart_captures: Vec::new(),
         })
     }
 }
@@ -147,6 +230,19 @@ pub enum PlatformDescriptionKind {
 pub struct PlatformDescription {
     content: String,
     kind: PlatformDescriptionKind,
+    /// The local file the description comes from
+    source: Option<PathBuf>,
+    /// The files of a generated description, itself first and then the local files it imports
+    imports: Vec<GeneratedImport>,
+}
+
+/// A local platform description file that is substituted and generated into the output directory.
+/// The `using` paths of the generated files refer to each other by `file_name`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct GeneratedImport {
+    pub file_name: String,
+    pub source: PathBuf,
+    pub content: String,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
@@ -159,15 +255,27 @@ pub enum PlatformDescriptionError {
     Io(String, String),
     #[error("Could not determine a file name for local file '{_0}'")]
     FileName(String),
+    #[error(
+        "The renode platform description '{path}' could not be found in '{root}'{}",
+        suggestion.as_ref().map(|s| format!(", did you mean '{s}'?")).unwrap_or_default()
+    )]
+    InternalNotFound {
+        path: String,
+        root: String,
+        suggestion: Option<String>,
+    },
     #[error(transparent)]
     EnvSub(#[from] EnvSubError),
 }
 
 impl PlatformDescription {
-    pub fn new(desc_from_config: &str) -> Result<Self, PlatformDescriptionError> {
-        // TODO - indentation stuff will depend on kind
-        let unindented_desc = unindent(desc_from_config.trim());
-        let desc = unindented_desc.as_str();
+    /// `reserved` holds the names of the files already generated into the output directory,
+    /// which a generated description and its imports are numbered around
+    pub fn new(
+        desc_from_config: &str,
+        reserved: &[String],
+    ) -> Result<Self, PlatformDescriptionError> {
+        let desc = desc_from_config.trim();
 
         // Heuristic to see if this is a local repl file for desc string
         let num_lines = desc.lines().count();
@@ -180,13 +288,17 @@ impl PlatformDescription {
             Ok(PlatformDescription {
                 content: desc.to_owned(),
                 kind: PlatformDescriptionKind::Internal,
+                source: None,
+                imports: Vec::new(),
             })
         } else if num_lines == 1 && !begins_with_import && ends_with_repl {
             let local_path = envsub(desc)?;
             if Path::new(&local_path).exists() {
                 Ok(PlatformDescription {
+                    source: Some(PathBuf::from(&local_path)),
                     content: local_path,
                     kind: PlatformDescriptionKind::LocalFile,
+                    imports: Vec::new(),
                 })
             } else {
                 Err(PlatformDescriptionError::LocalFileNotFound(local_path))
@@ -198,29 +310,33 @@ impl PlatformDescription {
             if !p.exists() {
                 return Err(PlatformDescriptionError::LocalFileNotFound(local_path));
             }
-            let file_name = p
-                .file_name()
+            p.file_name()
                 .and_then(|n| n.to_str())
                 .ok_or_else(|| PlatformDescriptionError::FileName(local_path.clone()))?;
+            let file_name = unique_file_name(p, reserved, &[]);
             let content =
                 envsub(&fs::read_to_string(p).map_err(|e| {
                     PlatformDescriptionError::Io(local_path.clone(), e.to_string())
                 })?)?;
+            let mut imports = vec![GeneratedImport {
+                file_name: file_name.clone(),
+                source: canonical(p)?,
+                content: String::new(),
+            }];
+            let content = import_usings(p, &content, reserved, &mut imports)?;
+            imports[0].content = content.clone();
             Ok(PlatformDescription {
                 content,
-                kind: PlatformDescriptionKind::GeneratedLocalFile(file_name.to_owned()),
+                kind: PlatformDescriptionKind::GeneratedLocalFile(file_name),
+                source: Some(p.to_path_buf()),
+                imports,
             })
         } else {
-            // TODO - indentation logic needs improved
-            let raw_content = envsub(desc)?;
-            let content = if !raw_content.starts_with("using") {
-                indent::indent_by(4, &raw_content)
-            } else {
-                raw_content
-            };
             Ok(PlatformDescription {
-                content,
+                content: envsub(&repl::normalize_indentation(desc_from_config))?,
                 kind: PlatformDescriptionKind::String,
+                source: None,
+                imports: Vec::new(),
             })
         }
     }
@@ -233,6 +349,39 @@ impl PlatformDescription {
         &self.kind
     }
 
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    pub fn imports(&self) -> &[GeneratedImport] {
+        &self.imports
+    }
+
+    /// The file names and contents to write into the output directory
+    pub fn generated_files(&self) -> Vec<(&str, &str)> {
+        self.imports
+            .iter()
+            .map(|i| (i.file_name.as_str(), i.content.as_str()))
+            .collect()
+    }
+
+    /// Checks that a renode platform description (`@platforms/...`) exists in the renode installation
+    pub fn check_internal(&self, renode_root: &Path) -> Result<(), PlatformDescriptionError> {
+        if self.kind != PlatformDescriptionKind::Internal {
+            return Ok(());
+        }
+        let relative = self.content.trim_start_matches(RESC_PATH_PREFIX);
+        if renode_root.join(relative).is_file() {
+            return Ok(());
+        }
+        let candidates = platforms::files(renode_root).unwrap_or_default();
+        Err(PlatformDescriptionError::InternalNotFound {
+            path: self.content.clone(),
+            root: renode_root.display().to_string(),
+            suggestion: platforms::closest(&self.content, &candidates).map(str::to_owned),
+        })
+    }
+
     pub(crate) fn resc_fmt(&self) -> String {
         match self.kind() {
             PlatformDescriptionKind::Internal => self.content().to_owned(),
@@ -247,8 +396,74 @@ impl PlatformDescription {
     }
 }
 
+/// Imports the local files a generated description uses, substituting them and rewriting the `using` paths
+/// to their generated file names. `imports` holds the files imported so far, the description itself first.
+fn import_usings(
+    path: &Path,
+    content: &str,
+    reserved: &[String],
+    imports: &mut Vec<GeneratedImport>,
+) -> Result<String, PlatformDescriptionError> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut result = Ok(());
+    let content = repl::rewrite_usings(content, |using| {
+        let local = dir.join(using);
+        // Other imports are resolved by renode, relative to its root
+        if result.is_err() || !local.is_file() {
+            return None;
+        }
+        let imported = canonical(&local).and_then(|source| {
+            if let Some(i) = imports.iter().find(|i| i.source == source) {
+                return Ok(i.file_name.clone());
+            }
+            let file_name = unique_file_name(&local, reserved, imports);
+            let index = imports.len();
+            imports.push(GeneratedImport {
+                file_name: file_name.clone(),
+                source,
+                content: String::new(),
+            });
+            let raw = fs::read_to_string(&local).map_err(|e| {
+                PlatformDescriptionError::Io(local.display().to_string(), e.to_string())
+            })?;
+            imports[index].content = import_usings(&local, &envsub(&raw)?, reserved, imports)?;
+            Ok(file_name)
+        });
+        match imported {
+            Ok(file_name) => Some(file_name),
+            Err(e) => {
+                result = Err(e);
+                None
+            }
+        }
+    });
+    result.map(|_| content)
+}
+
+fn canonical(path: &Path) -> Result<PathBuf, PlatformDescriptionError> {
+    path.canonicalize()
+        .map_err(|e| PlatformDescriptionError::Io(path.display().to_string(), e.to_string()))
+}
+
+/// The file's name, numbered when another generated file already has it
+fn unique_file_name(path: &Path, reserved: &[String], imports: &[GeneratedImport]) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let taken = |name: &str| {
+        reserved.iter().any(|r| r == name) || imports.iter().any(|i| i.file_name == name)
+    };
+    let mut file_name = format!("{stem}.{REPL_FILE_EXT}");
+    let mut n = 1;
+    while taken(&file_name) {
+        file_name = format!("{stem}_{n}.{REPL_FILE_EXT}");
+        n += 1;
+    }
+    file_name
+}
+
 // TODO sealed trait, AsResc or w/e that provides formated resc script syntax lines
-// maybe deals with repl indentation stuff too
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
 pub enum RescGenericFieldError {
@@ -407,3 +622,330 @@ impl PostStartCommand {
         }
     }
 }
+
+/// Cortex-M semihosting on a CPU, with exit hooks at each semihosting breakpoint
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Semihosting {
+    pub cpu: String,
+    pub breakpoints: Vec<u64>,
+}
+
+impl Semihosting {
+    pub fn new<P: AsRef<Path>>(
+        cfg: &SemihostingConfig,
+        elf_path: P,
+    ) -> Result<Self, RescDefinitionError> {
+        let cpu = envsub(cfg.cpu.trim())?;
+        if cpu.is_empty() {
+            return Err(RescGenericFieldError::Empty("semihosting.cpu").into());
+        }
+        let breakpoints = ElfFile::read(elf_path)?.thumb_instruction_addresses(BKPT_SEMIHOSTING)?;
+        Ok(Self { cpu, breakpoints })
+    }
+
+    /// The CPU's name in platform descriptions, used as the registration point
+    pub(crate) fn cpu_local_name(&self) -> &str {
+        self.cpu.rsplit('.').next().unwrap_or(&self.cpu)
+    }
+
+    pub(crate) fn uart(&self) -> String {
+        format!("{}.semihosting", self.cpu)
+    }
+}
+
+/// The renode GDB server, for the CPUs of a cluster or all of them when none is given.
+/// Mirrors `machine StartGdbServer port autostartEmulation cpuCluster`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct GdbServer {
+    pub port: u16,
+    /// Renode starts the emulation when GDB attaches, so the script doesn't start it
+    pub autostart_emulation: bool,
+    pub cpu_cluster: Option<String>,
+}
+
+impl GdbServer {
+    pub fn new(cfg: &GdbConfig) -> Result<Self, RescDefinitionError> {
+        let cpu_cluster = cfg
+            .cpu_cluster
+            .as_ref()
+            .map(|c| envsub(c.trim()))
+            .transpose()?;
+        if cpu_cluster.as_ref().is_some_and(|c| c.is_empty()) {
+            return Err(RescGenericFieldError::Empty("gdb.cpu-cluster").into());
+        }
+        Ok(Self {
+            port: cfg
+                .port
+                .fixed()
+                .ok_or(RescDefinitionError::UnallocatedPort("gdb.port"))?,
+            autostart_emulation: cfg.autostart_emulation,
+            cpu_cluster,
+        })
+    }
+
+    pub(crate) fn resc_fmt(&self) -> String {
+        match self.cpu_cluster.as_ref() {
+            Some(cluster) => format!(
+                "machine StartGdbServer {} {} \"{cluster}\"",
+                self.port, self.autostart_emulation
+            ),
+            None => format!(
+                "machine StartGdbServer {} {}",
+                self.port, self.autostart_emulation
+            ),
+        }
+    }
+}
+
+/// A UART connected to a TCP server socket terminal
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct SocketTerminal {
+    pub uart: String,
+    pub port: u16,
+    pub name: String,
+}
+
+impl SocketTerminal {
+    pub fn new(cfg: &SocketTerminalConfig) -> Result<Self, RescDefinitionError> {
+        let uart = envsub(cfg.uart.trim())?;
+        if uart.is_empty() {
+            return Err(RescGenericFieldError::Empty("socket-terminals.uart").into());
+        }
+        let port = cfg
+            .port
+            .fixed()
+            .ok_or(RescDefinitionError::UnallocatedPort(
+                "socket-terminals.port",
+            ))?;
+        let name = match cfg.name.as_ref() {
+            Some(n) => envsub(n.trim())?,
+            None => format!("{}_socket", uart.rsplit('.').next().unwrap_or(&uart)),
+        };
+        if name.is_empty() {
+            return Err(RescGenericFieldError::Empty("socket-terminals.name").into());
+        }
+        Ok(Self { uart, port, name })
+    }
+
+    pub(crate) fn resc_fmt(&self) -> String {
+        format!(
+            "emulation CreateServerSocketTerminal {} \"{}\" false\nconnector Connect {} {}",
+            self.port, self.name, self.uart, self.name
+        )
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum LoggingError {
+    #[error(
+        "Invalid log level '{_0}', expected one of noisy, debug, info, warning, error or -1..=3"
+    )]
+    InvalidLevel(String),
+    #[error("The field '{_0}' cannot contain an empty string")]
+    Empty(&'static str),
+    #[error(transparent)]
+    EnvSub(#[from] EnvSubError),
+}
+
+/// Renode's log levels
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum LogLevel {
+    Noisy,
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = LoggingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "noisy" | "-1" => Ok(LogLevel::Noisy),
+            "debug" | "0" => Ok(LogLevel::Debug),
+            "info" | "1" => Ok(LogLevel::Info),
+            "warning" | "2" => Ok(LogLevel::Warning),
+            "error" | "3" => Ok(LogLevel::Error),
+            _ => Err(LoggingError::InvalidLevel(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = match self {
+            LogLevel::Noisy => -1,
+            LogLevel::Debug => 0,
+            LogLevel::Info => 1,
+            LogLevel::Warning => 2,
+            LogLevel::Error => 3,
+        };
+        write!(f, "{v}")
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Logging {
+    pub level: Option<LogLevel>,
+    pub peripherals: Vec<(String, LogLevel)>,
+    pub file: Option<String>,
+    pub file_level: Option<LogLevel>,
+    pub function_names: Vec<String>,
+    pub peripheral_access: Vec<String>,
+}
+
+impl Logging {
+    pub fn new(cfg: &LoggingConfig) -> Result<Self, LoggingError> {
+        let level = |l: &Option<String>| -> Result<Option<LogLevel>, LoggingError> {
+            l.as_ref().map(|l| envsub(l)?.parse()).transpose()
+        };
+        let names = |field: &'static str, v: &[String]| -> Result<Vec<String>, LoggingError> {
+            v.iter()
+                .map(|n| {
+                    let n = envsub(n.trim())?;
+                    if n.is_empty() {
+                        Err(LoggingError::Empty(field))
+                    } else {
+                        Ok(n)
+                    }
+                })
+                .collect()
+        };
+
+        let mut peripherals = Vec::new();
+        for (p, l) in cfg.peripherals.iter() {
+            let p = envsub(p.trim())?;
+            if p.is_empty() {
+                return Err(LoggingError::Empty("logging.peripherals"));
+            }
+            peripherals.push((p, envsub(l)?.parse()?));
+        }
+
+        let file = cfg.file.as_ref().map(|f| envsub(f.trim())).transpose()?;
+        if file.as_deref() == Some("") {
+            return Err(LoggingError::Empty("logging.file"));
+        }
+
+        Ok(Self {
+            level: level(&cfg.level)?,
+            peripherals,
+            file,
+            file_level: level(&cfg.file_level)?,
+            function_names: names("logging.log-function-names", &cfg.log_function_names)?,
+            peripheral_access: names("logging.log-peripheral-access", &cfg.log_peripheral_access)?,
+        })
+    }
+
+    /// Commands for after the machine is created
+    pub(crate) fn resc_fmt_machine(&self) -> Vec<String> {
+        let mut cmds = Vec::new();
+        if let Some(f) = self.file.as_ref() {
+            cmds.push(format!("logFile @{f} true"));
+        }
+        if let Some(l) = self.level {
+            cmds.push(format!("logLevel {l}"));
+        }
+        if let Some(l) = self.file_level {
+            cmds.push(format!("logLevel {l} file"));
+        }
+        cmds
+    }
+
+    /// Commands for after the peripherals are loaded
+    pub(crate) fn resc_fmt_peripherals(&self) -> Vec<String> {
+        let mut cmds = Vec::new();
+        for (p, l) in self.peripherals.iter() {
+            cmds.push(format!("logLevel {l} {p}"));
+        }
+        for p in self.peripheral_access.iter() {
+            cmds.push(format!("sysbus LogPeripheralAccess {p} true"));
+        }
+        for cpu in self.function_names.iter() {
+            cmds.push(format!("{cpu} LogFunctionNames true"));
+        }
+        cmds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_levels() {
+        assert_eq!("noisy".parse(), Ok(LogLevel::Noisy));
+        assert_eq!("Warning".parse(), Ok(LogLevel::Warning));
+        assert_eq!("3".parse(), Ok(LogLevel::Error));
+        assert_eq!(
+            "warn".parse::<LogLevel>(),
+            Err(LoggingError::InvalidLevel("warn".to_owned()))
+        );
+        assert_eq!(LogLevel::Noisy.to_string(), "-1");
+        assert_eq!(LogLevel::Info.to_string(), "1");
+    }
+
+    #[test]
+    fn gdb_server() {
+        let mut cfg = GdbConfig::default();
+        assert_eq!(
+            GdbServer::new(&cfg).unwrap().resc_fmt(),
+            "machine StartGdbServer 3333 false"
+        );
+        cfg.autostart_emulation = true;
+        cfg.cpu_cluster = Some("all".to_owned());
+        assert_eq!(
+            GdbServer::new(&cfg).unwrap().resc_fmt(),
+            "machine StartGdbServer 3333 true \"all\""
+        );
+        cfg.cpu_cluster = Some(" ".to_owned());
+        assert!(GdbServer::new(&cfg).is_err());
+    }
+
+    #[test]
+    fn generated_imports() {
+        let dir = tempfile::tempdir().unwrap();
+        let cpu = dir.path().join("cpu");
+        fs::create_dir_all(&cpu).unwrap();
+        fs::write(
+            dir.path().join("board.repl"),
+            "using \"cpu/mcu.repl\"\nusing \"common.repl\"\nusing \"platforms/cpus/stm32f4.repl\"\n",
+        )
+        .unwrap();
+        fs::write(
+            cpu.join("mcu.repl"),
+            "using \"common.repl\"\nsram: Memory.MappedMemory @ sysbus ${GENERATED_IMPORTS_SRAM:-0x20000000}\n",
+        )
+        .unwrap();
+        fs::write(cpu.join("common.repl"), "using \"../board.repl\"\n").unwrap();
+        fs::write(dir.path().join("common.repl"), "using \"cpu/mcu.repl\"\n").unwrap();
+
+        let board = format!("< {}", dir.path().join("board.repl").display());
+        let desc = PlatformDescription::new(&board, &[]).unwrap();
+        assert_eq!(
+            desc.content(),
+            "using \"mcu.repl\"\nusing \"common_1.repl\"\nusing \"platforms/cpus/stm32f4.repl\"\n"
+        );
+        assert_eq!(
+            desc.generated_files(),
+            vec![
+                ("board.repl", desc.content()),
+                (
+                    "mcu.repl",
+                    "using \"common.repl\"\nsram: Memory.MappedMemory @ sysbus 0x20000000\n"
+                ),
+                ("common.repl", "using \"board.repl\"\n"),
+                ("common_1.repl", "using \"mcu.repl\"\n"),
+            ]
+        );
+
+        // Numbered around the files generated by the other descriptions and the assets
+        let reserved = vec!["board.repl".to_owned(), "mcu.repl".to_owned()];
+        let other = PlatformDescription::new(&board, &reserved).unwrap();
+        assert_eq!(other.resc_fmt(), "@board_1.repl");
+        assert_eq!(
+            other.content(),
+            "using \"mcu_1.repl\"\nusing \"common_1.repl\"\nusing \"platforms/cpus/stm32f4.repl\"\n"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, thiserror::Error)]
+pub enum WatchError {
+    #[error("Watch mode requires the renode monitor 'port' to be configured")]
+    MissingMonitorPort,
+    #[error("The watch build command is empty")]
+    EmptyBuildCommand,
+}
+
+/// Detects changes to files by polling their modification times.
+/// Directories are watched recursively, skipping hidden directories and `target`.
+pub struct MtimeWatcher {
+    paths: Vec<PathBuf>,
+    mtimes: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl MtimeWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let mtimes = snapshot(&paths);
+        MtimeWatcher { paths, mtimes }
+    }
+
+    /// Returns true if a file was added, removed or modified since the previous check
+    pub fn changed(&mut self) -> bool {
+        let mtimes = snapshot(&self.paths);
+        if mtimes != self.mtimes {
+            self.mtimes = mtimes;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn snapshot(paths: &[PathBuf]) -> BTreeMap<PathBuf, SystemTime> {
+    let mut mtimes = BTreeMap::new();
+    for p in paths.iter() {
+        visit(p, &mut mtimes);
+    }
+    mtimes
+}
+
+fn visit(path: &Path, mtimes: &mut BTreeMap<PathBuf, SystemTime>) {
+    let Ok(metadata) = fs::metadata(path) else {
+        // Missing paths are fine, they're treated as removed
+        return;
+    };
+    if metadata.is_dir() {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let p = entry.path();
+            let skip = p.is_dir()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with('.') || n == "target");
+            if !skip {
+                visit(&p, mtimes);
+            }
+        }
+    } else if let Ok(mtime) = metadata.modified() {
+        mtimes.insert(path.to_path_buf(), mtime);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("target")).unwrap();
+        fs::write(src.join("main.rs"), "fn main() {}").unwrap();
+
+        let mut w = MtimeWatcher::new(vec![src.clone(), dir.path().join("memory.x")]);
+        assert!(!w.changed());
+
+        fs::write(src.join("target").join("app"), "").unwrap();
+        assert!(!w.changed());
+
+        fs::write(dir.path().join("memory.x"), "MEMORY {}").unwrap();
+        assert!(w.changed());
+        assert!(!w.changed());
+
+        fs::remove_file(src.join("main.rs")).unwrap();
+        assert!(w.changed());
+    }
+}